@@ -0,0 +1,179 @@
+/// How a bank prints amounts, and how it marks credits such as refunds and payments.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AmountFormat {
+    /// "(3.85)" is a credit.
+    pub parentheses_negative: bool,
+    /// Suffix marking credits, such as "CR", empty when there is none.
+    pub credit_suffix: String,
+    /// Suffix marking charges, such as "DR", empty when there is none.
+    pub debit_suffix: String,
+    /// "," for statements printing "1.234,56". The other of "," and "." separates thousands.
+    pub decimal_separator: char,
+    /// Charges are printed negative and credits positive.
+    pub charges_negative: bool,
+}
+
+impl Default for AmountFormat {
+    fn default() -> Self {
+        Self {
+            parentheses_negative: true,
+            credit_suffix: "CR".to_string(),
+            debit_suffix: "DR".to_string(),
+            decimal_separator: '.',
+            charges_negative: false,
+        }
+    }
+}
+
+fn strip_suffix_ignore_case<'a>(text: &'a str, suffix: &str) -> Option<&'a str> {
+    let start = text.len().checked_sub(suffix.len())?;
+    (!suffix.is_empty() && text.is_char_boundary(start) && text[start..].eq_ignore_ascii_case(suffix))
+        .then(|| text[..start].trim_end())
+}
+
+impl AmountFormat {
+    /// Signed amount of `text` such as "1,234.56 CR", "(3.85)", "-12.00" or "12.00-", credits
+    /// negative. Text which is not an amount is an error.
+    ///
+    /// ```
+    /// use credit_card_billsplit::bill_reader::AmountFormat;
+    ///
+    /// let format = AmountFormat::default();
+    /// assert_eq!(format.parse("1,234.56 CR"), Ok(-1234.56));
+    /// assert_eq!(format.parse("12.00-"), Ok(-12.0));
+    /// assert!(format.parse("1.234,56").is_err());
+    /// let comma = AmountFormat { decimal_separator: ',', ..Default::default() };
+    /// assert_eq!(comma.parse("1.234,56"), Ok(1234.56));
+    /// ```
+    pub fn parse(&self, text: &str) -> Result<f64, String> {
+        let error = |reason: &str| format!("\"{}\" is not an amount, {}", text, reason);
+        let mut amount = text.trim();
+        let mut negative = false;
+        if let Some(rest) = strip_suffix_ignore_case(amount, &self.credit_suffix) {
+            amount = rest;
+            negative = true;
+        } else if let Some(rest) = strip_suffix_ignore_case(amount, &self.debit_suffix) {
+            amount = rest;
+        }
+        if let Some(inner) = amount.strip_prefix('(').and_then(|a| a.strip_suffix(')')) {
+            if !self.parentheses_negative {
+                return Err(error("parentheses are not used for credits"));
+            }
+            if negative {
+                return Err(error("it is marked as a credit twice"));
+            }
+            amount = inner.trim();
+            negative = true;
+        }
+        let minus = ['-', '\u{2212}'];
+        if let Some(rest) = amount.strip_prefix(minus).or_else(|| amount.strip_suffix(minus)) {
+            if negative {
+                return Err(error("it is marked as a credit twice"));
+            }
+            amount = rest.trim();
+            negative = true;
+        } else if let Some(rest) = amount.strip_prefix('+') {
+            amount = rest.trim();
+        }
+
+        let thousands = if self.decimal_separator == ',' { '.' } else { ',' };
+        let (integer, fraction) = amount.split_once(self.decimal_separator).unwrap_or((amount, ""));
+        if let Some(c) = fraction.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(match c {
+                c if c == self.decimal_separator => error("it has two decimal separators"),
+                ',' | '.' => error(&format!("the decimal separator is \"{}\"", self.decimal_separator)),
+                c => error(&format!("\"{}\" is not expected", c)),
+            });
+        }
+        //1,234,567: a first group of up to 3 digits, then groups of exactly 3
+        let groups = integer.split(thousands).collect::<Vec<&str>>();
+        for (i, group) in groups.iter().enumerate() {
+            if let Some(c) = group.chars().find(|c| !c.is_ascii_digit()) {
+                return Err(error(&format!("\"{}\" is not expected", c)));
+            }
+            let digits = if i == 0 { 1..=3 } else { 3..=3 };
+            if groups.len() > 1 && !digits.contains(&group.len()) {
+                return Err(error(&format!("\"{}\" does not separate thousands", thousands)));
+            }
+        }
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(error("it has no digits"));
+        }
+        let number = format!("{}.{}", groups.concat(), fraction);
+        let value = number.parse::<f64>().map_err(|e| error(&e.to_string()))?;
+        let value = if negative { -value } else { value };
+        Ok(if self.charges_negative { -value } else { value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credit_and_debit_suffixes() {
+        let format = AmountFormat::default();
+        assert_eq!(format.parse("12.34 CR"), Ok(-12.34));
+        assert_eq!(format.parse("12.34cr"), Ok(-12.34));
+        assert_eq!(format.parse("12.34 DR"), Ok(12.34));
+        assert!(format.parse("(12.34) CR").is_err());
+    }
+
+    #[test]
+    fn minus_signs() {
+        let format = AmountFormat::default();
+        assert_eq!(format.parse("-12.34"), Ok(-12.34));
+        assert_eq!(format.parse("12.34-"), Ok(-12.34));
+        assert_eq!(format.parse("\u{2212}12.34"), Ok(-12.34));
+        assert_eq!(format.parse("+12.34"), Ok(12.34));
+        assert!(format.parse("-12.34 CR").is_err());
+    }
+
+    #[test]
+    fn parentheses() {
+        assert_eq!(AmountFormat::default().parse("(1,234.56)"), Ok(-1234.56));
+        let format = AmountFormat { parentheses_negative: false, ..Default::default() };
+        assert!(format.parse("(1,234.56)").is_err());
+    }
+
+    #[test]
+    fn charges_negative() {
+        let format = AmountFormat { charges_negative: true, ..Default::default() };
+        assert_eq!(format.parse("-12.34"), Ok(12.34));
+        assert_eq!(format.parse("12.34"), Ok(-12.34));
+    }
+
+    #[test]
+    fn thousands_separators() {
+        let format = AmountFormat::default();
+        assert_eq!(format.parse("1,234,567.89"), Ok(1234567.89));
+        assert_eq!(format.parse("1234567.89"), Ok(1234567.89));
+        assert_eq!(format.parse(".50"), Ok(0.5));
+        assert!(format.parse("1,23.45").is_err());
+        assert!(format.parse("1234,567.00").is_err());
+        assert!(format.parse(",123.00").is_err());
+        assert!(format.parse("1 234.56").is_err());
+        assert!(format.parse("1'234.56").is_err());
+    }
+
+    #[test]
+    fn decimal_comma() {
+        let format = AmountFormat { decimal_separator: ',', ..Default::default() };
+        assert_eq!(format.parse("1.234,56"), Ok(1234.56));
+        assert_eq!(format.parse("12,34 CR"), Ok(-12.34));
+        assert!(format.parse("1,234.56").is_err());
+        assert!(format.parse("1.23,45").is_err());
+        assert!(format.parse("1,2,3").is_err());
+    }
+
+    #[test]
+    fn not_amounts() {
+        let format = AmountFormat::default();
+        assert!(format.parse("").is_err());
+        assert!(format.parse(".").is_err());
+        assert!(format.parse("CR").is_err());
+        assert!(format.parse("12.34.56").is_err());
+        assert!(format.parse("USD 12.34").is_err());
+    }
+}
@@ -1,21 +1,202 @@
-use rfd::{AsyncFileDialog, AsyncMessageDialog, FileHandle, MessageButtons, MessageLevel};
+use rfd::{AsyncFileDialog, AsyncMessageDialog, MessageButtons, MessageLevel};
 use std::default::Default;
+use std::future::Future;
 use std::sync::{Arc, Mutex};
-use egui::{Button, Color32, Layout, Margin, RichText, Vec2, Window};
-use itertools::Itertools;
-use crate::bill_reader::{BillReader, CreditCardBillReader, Transaction, JOINT_TAG, PERSONAL_TAG};
+use egui::{Button, RichText, TextEdit, Window};
+use crate::attachment::{self, Attachment, RECEIPT_EXTENSIONS};
+use crate::background::{Outcome, ParseJob};
+use crate::bill_reader::{LineKind, PlainTextExtractor, ReaderOptions, Statement, StatementParser, StatementProfile, Transaction, JOINT_TAG, PERSONAL_TAG};
+use crate::budget::{self, Budget, BudgetPeriod};
+use crate::currency::{CurrencySettings, RateTable};
+use crate::dashboard;
+use crate::export;
+use crate::formatting::{FormatRule, Formatter, RowFormat};
+use crate::history::{self, StatementHistory, StatementKey};
+use crate::import::ImportResult;
+use crate::inspector::{self, StatementInspector};
+use crate::ledger::{self, AccountMapping, LedgerFormat, LedgerSettings};
+use crate::merchant::{MerchantAlias, MerchantNormaliser};
+use crate::palette::{Command, Palette};
+use crate::query::{Query, SavedView, Sort, SortColumn};
+use crate::report;
+use crate::share::{self, Snapshot};
+use crate::shortcuts::{self, Action, Binding, Shortcuts, TagShortcut};
+use crate::summary::SplitSettings;
+use crate::viewer::StatementViewer;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn execute<F: Future<Output = ()> + Send + 'static>(f: F) {
+    std::thread::spawn(move || async_std::task::block_on(f));
+}
+
+#[cfg(target_arch = "wasm32")]
+fn execute<F: Future<Output = ()> + 'static>(f: F) {
+    wasm_bindgen_futures::spawn_local(f);
+}
+
+async fn show_error(description: String) {
+    AsyncMessageDialog::new()
+        .set_level(MessageLevel::Error)
+        .set_title("Error")
+        .set_description(description)
+        .set_buttons(MessageButtons::Ok)
+        .show()
+        .await;
+}
+
+async fn show_warning(description: String) {
+    AsyncMessageDialog::new()
+        .set_level(MessageLevel::Warning)
+        .set_title("Warning")
+        .set_description(description)
+        .set_buttons(MessageButtons::Ok)
+        .show()
+        .await;
+}
+
+async fn save_as(file_name: String, data: Vec<u8>) {
+    let extension = file_name.rsplit_once('.')
+        .map(|(_, e)| e.to_string())
+        .unwrap_or_default();
+    let Some(file) = AsyncFileDialog::new()
+        .add_filter(&extension, &[&extension])
+        .set_file_name(&file_name)
+        .save_file()
+        .await else {
+        return;
+    };
+    if let Err(e) = file.write(&data).await {
+        show_error(e.to_string()).await;
+    }
+}
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct BillSplitApp {
-    transactions: Arc<Mutex<Vec<Transaction>>>
+    transactions: Arc<Mutex<Vec<Transaction>>>,
+    merchant_aliases: Vec<MerchantAlias>,
+    show_clean_description: bool,
+    show_statement_viewer: bool,
+    split_settings: SplitSettings,
+    ledger_settings: LedgerSettings,
+    share_url: String,
+    share_notes: bool,
+    history: StatementHistory,
+    current_statement: Option<StatementKey>,
+    budgets: Vec<Budget>,
+    #[serde(skip)]
+    show_budgets: bool,
+    #[serde(skip)]
+    show_dashboard: bool,
+    saved_views: Vec<SavedView>,
+    format_rules: Vec<FormatRule>,
+    currency_settings: CurrencySettings,
+    shortcuts: Shortcuts,
+    statement_profiles: Vec<StatementProfile>,
+    #[serde(skip)]
+    search: String,
+    #[serde(skip)]
+    sort: Option<Sort>,
+    #[serde(skip)]
+    view_name: String,
+    #[serde(skip)]
+    read_only: bool,
+    #[serde(skip)]
+    show_merchant_aliases: bool,
+    #[serde(skip)]
+    show_settings: bool,
+    #[serde(skip)]
+    selected: Option<usize>,
+    #[serde(skip)]
+    focused: Option<usize>,
+    #[serde(skip)]
+    scroll_to_focus: bool,
+    #[serde(skip)]
+    palette: Palette,
+    #[serde(skip)]
+    show_shortcuts: bool,
+    #[serde(skip)]
+    rebinding: Option<Binding>,
+    #[serde(skip)]
+    import_results: Vec<ImportResult>,
+    #[serde(skip)]
+    parse_job: Option<ParseJob>,
+    /// Imported statements not applied yet, the first one waits for the user to agree to replace
+    /// the one in the history.
+    #[serde(skip)]
+    pending_imports: Vec<(Option<StatementKey>, Vec<Transaction>)>,
+    #[serde(skip)]
+    imported_rates: Option<std::sync::mpsc::Receiver<RateTable>>,
+    #[serde(skip)]
+    viewer: StatementViewer,
+    #[serde(skip)]
+    inspector: StatementInspector,
+    #[serde(skip)]
+    show_profiles: bool,
+    #[serde(skip)]
+    imported_profiles: Option<std::sync::mpsc::Receiver<Vec<StatementProfile>>>,
+    #[serde(skip)]
+    profile_check: ProfileCheck,
+}
+
+/// Sample text checked against one profile in the statement profiles window.
+#[derive(Default)]
+struct ProfileCheck {
+    profile: usize,
+    sample: String,
+    //refreshed when the profile or the sample changes
+    result: Option<Result<CheckResult, String>>,
+}
+
+struct CheckResult {
+    detected: bool,
+    lines: Vec<(String, LineKind)>,
+    statement: Statement,
 }
 
 impl Default for BillSplitApp {
     fn default() -> Self {
         Self {
-            transactions: Arc::new(Mutex::new(Vec::<Transaction>::new()))
+            transactions: Arc::new(Mutex::new(Vec::<Transaction>::new())),
+            merchant_aliases: Vec::new(),
+            show_clean_description: true,
+            show_statement_viewer: false,
+            split_settings: SplitSettings::default(),
+            ledger_settings: LedgerSettings::default(),
+            share_url: String::new(),
+            share_notes: false,
+            history: StatementHistory::default(),
+            current_statement: None,
+            budgets: Vec::new(),
+            show_budgets: false,
+            show_dashboard: false,
+            saved_views: Vec::new(),
+            format_rules: FormatRule::defaults(),
+            currency_settings: CurrencySettings::default(),
+            shortcuts: Shortcuts::default(),
+            statement_profiles: Vec::new(),
+            search: String::new(),
+            sort: None,
+            view_name: String::new(),
+            read_only: false,
+            show_merchant_aliases: false,
+            show_settings: false,
+            selected: None,
+            focused: None,
+            scroll_to_focus: false,
+            palette: Palette::default(),
+            show_shortcuts: false,
+            rebinding: None,
+            import_results: Vec::new(),
+            parse_job: None,
+            pending_imports: Vec::new(),
+            imported_rates: None,
+            viewer: StatementViewer::default(),
+            inspector: StatementInspector::default(),
+            show_profiles: false,
+            imported_profiles: None,
+            profile_check: ProfileCheck::default(),
         }
     }
 }
@@ -26,15 +207,244 @@ impl BillSplitApp {
         // This is also where you can customize the look and feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
+        #[cfg(target_arch = "wasm32")]
+        if let Some(app) = Self::from_location_snapshot() {
+            return app;
+        }
+
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
         if let Some(storage) = cc.storage {
-            return eframe::get_value::<BillSplitApp>(storage, eframe::APP_KEY).unwrap_or_default();
+            let mut app = eframe::get_value::<BillSplitApp>(storage, eframe::APP_KEY).unwrap_or_default();
+            //transactions saved before merchant normalisation existed have no clean name yet
+            app.normalise_merchants();
+            //statements saved before the currency was recorded are in the home currency of the time
+            app.currency_settings.stamp(&mut app.transactions.lock().unwrap());
+            for statement in &mut app.history.statements {
+                app.currency_settings.stamp(&mut statement.transactions);
+            }
+            //state saved before statements had a key, their history entries only have the month
+            let key = app.current_statement.take().or_else(|| {
+                history::statement_month(&app.transactions.lock().unwrap(), None)
+                    .map(|month| StatementKey { month, ..Default::default() })
+            });
+            app.set_current_statement(key);
+            return app;
         }
 
         Default::default()
     }
 
+    /// A "#snapshot=..." link opens the shared statement read-only, without touching the saved
+    /// state of the visitor.
+    #[cfg(target_arch = "wasm32")]
+    fn from_location_snapshot() -> Option<Self> {
+        let hash = web_sys::window()?.location().hash().ok()?;
+        let encoded = hash.strip_prefix('#')?.strip_prefix(share::SNAPSHOT_FRAGMENT)?;
+        match Snapshot::decode(encoded) {
+            Ok(snapshot) => Some(Self {
+                //the amounts were converted to the home currency of whoever shared it
+                currency_settings: CurrencySettings {
+                    home: snapshot.transactions.first()
+                        .map(|t| t.currency.clone())
+                        .filter(|c| !c.is_empty())
+                        .unwrap_or_else(|| CurrencySettings::default().home),
+                    ..Default::default()
+                },
+                transactions: Arc::new(Mutex::new(snapshot.transactions)),
+                split_settings: snapshot.split_settings,
+                read_only: true,
+                ..Default::default()
+            }),
+            Err(e) => {
+                log::error!("{}", e);
+                None
+            }
+        }
+    }
+
+    fn share_base_url(&self) -> Option<String> {
+        #[cfg(target_arch = "wasm32")]
+        if let Some(href) = web_sys::window().and_then(|w| w.location().href().ok()) {
+            return Some(href);
+        }
+        (!self.share_url.is_empty()).then(|| self.share_url.clone())
+    }
+
+    /// Copies the open statement, with its latest tags and notes, into the history.
+    fn sync_history(&mut self) {
+        if let Some(key) = &self.current_statement {
+            self.history.store(key, &self.transactions.lock().unwrap());
+        }
+    }
+
+    /// The journal exports date entries in the year of the open statement, the year in the
+    /// ledger settings can still be changed before exporting.
+    fn set_current_statement(&mut self, key: Option<StatementKey>) {
+        if let Some(year) = key.as_ref().and_then(StatementKey::year) {
+            self.ledger_settings.year = year;
+        }
+        self.current_statement = key;
+    }
+
+    fn open_statement(&mut self, key: &StatementKey) {
+        self.sync_history();
+        let Some(statement) = self.history.get(key) else {
+            return;
+        };
+        *self.transactions.lock().unwrap() = statement.transactions.clone();
+        self.set_current_statement(Some(key.clone()));
+        self.selected = None;
+    }
+
+    fn warn_over_budget(&self) {
+        let Some(key) = &self.current_statement else {
+            return;
+        };
+        let over = budget::newly_over(&self.budgets, &self.history, key, &self.transactions.lock().unwrap(), &self.currency_settings)
+            .into_iter()
+            .map(|(b, spent)| format!("{} ({}): {:.2} of {:.2}", b.tag, b.period.name().to_lowercase(), spent, b.limit))
+            .collect::<Vec<String>>();
+        if !over.is_empty() {
+            execute(show_warning(format!("This statement puts these budgets over their limit:\n{}", over.join("\n"))));
+        }
+    }
+
+    fn build_budgets_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_budgets;
+        Window::new("Budgets")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label("Limits apply to the transactions with the tag, yearly budgets count from January.");
+                let mut remove = None;
+                egui::Grid::new("budgets_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Tag");
+                        ui.strong("Limit");
+                        ui.strong("Period");
+                        ui.end_row();
+                        for (index, budget) in self.budgets.iter_mut().enumerate() {
+                            ui.text_edit_singleline(&mut budget.tag);
+                            ui.add(egui::DragValue::new(&mut budget.limit).speed(10.0).range(0.0..=f64::MAX));
+                            egui::ComboBox::from_id_source(("budget_period", index))
+                                .selected_text(budget.period.name())
+                                .show_ui(ui, |ui| {
+                                    for period in BudgetPeriod::ALL {
+                                        ui.selectable_value(&mut budget.period, period, period.name());
+                                    }
+                                });
+                            if ui.button("Remove").clicked() {
+                                remove = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(index) = remove {
+                    self.budgets.remove(index);
+                }
+                if ui.button("Add budget").clicked() {
+                    self.budgets.push(Budget::default());
+                }
+
+                if self.budgets.is_empty() || self.history.statements.is_empty() {
+                    return;
+                }
+                ui.separator();
+                ui.strong("Budget vs actual");
+                let transactions = self.transactions.lock().unwrap();
+                egui::ScrollArea::both()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("budget_actual_grid")
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.strong("Statement");
+                                for budget in &self.budgets {
+                                    ui.strong(format!("{} ({})", budget.tag, budget.period.name().to_lowercase()));
+                                }
+                                ui.end_row();
+                                for statement in self.history.statements.iter().rev() {
+                                    let key = statement.key();
+                                    ui.label(key.label());
+                                    let current = if self.current_statement.as_ref() == Some(&key) {
+                                        &transactions[..]
+                                    } else {
+                                        &statement.transactions[..]
+                                    };
+                                    for budget in &self.budgets {
+                                        let spent = budget.spent(&self.history, &key, current, &self.currency_settings);
+                                        let text = RichText::new(format!("{:.2} / {:.2}", spent, budget.limit));
+                                        ui.label(if budget.is_over(spent) { text.color(ui.visuals().error_fg_color) } else { text });
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+        self.show_budgets = open;
+    }
+
+    fn normalise_merchants(&self) {
+        let normaliser = MerchantNormaliser::new(&self.merchant_aliases);
+        normaliser.apply(&mut self.transactions.lock().unwrap());
+    }
+
+    fn build_merchant_aliases_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_merchant_aliases;
+        let mut apply = false;
+        Window::new("Merchant aliases")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label("Pattern is a case-insensitive regex matched against the raw description.");
+                let mut remove = None;
+                egui::Grid::new("merchant_aliases_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Pattern");
+                        ui.strong("Name");
+                        ui.end_row();
+                        for (index, alias) in self.merchant_aliases.iter_mut().enumerate() {
+                            ui.text_edit_singleline(&mut alias.pattern);
+                            ui.text_edit_singleline(&mut alias.name);
+                            if ui.button("Remove").clicked() {
+                                remove = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(index) = remove {
+                    self.merchant_aliases.remove(index);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Add alias").clicked() {
+                        self.merchant_aliases.push(MerchantAlias::default());
+                    }
+                    if ui.button("Apply").clicked() {
+                        apply = true;
+                    }
+                });
+            });
+        self.show_merchant_aliases = open;
+
+        if apply {
+            self.normalise_merchants();
+        }
+    }
+
+    /// Tags the transaction with `tag` alone, or untags it when it already has the tag.
+    fn toggle_tag(transaction: &mut Transaction, tag: &str) {
+        match transaction.tags.iter().position(|t| t == tag) {
+            None => {
+                transaction.tags.clear();
+                transaction.tags.push(tag.to_string());
+            }
+            Some(s) => {
+                transaction.tags.remove(s);
+            }
+        }
+    }
+
     fn build_button(ui: &mut egui::Ui, transaction: &mut Transaction, content: &str){
         let mut button_text = RichText::new(content);
         if transaction.tags.contains(&content.to_string()) {
@@ -43,19 +453,1085 @@ impl BillSplitApp {
         let button = Button::new(button_text);
 
         if ui.add(button).clicked() {
-            match transaction.tags.iter().position(|t| {t == &content.to_string()}) {
-                None => {
-                    transaction.tags.clear();
-                    transaction.tags.push(content.to_string());
+            Self::toggle_tag(transaction, content);
+        };
+    }
+
+    fn attach_receipt(&self, ctx: &egui::Context, index: usize) {
+        let transactions = Arc::clone(&self.transactions);
+        //the row may move while the file dialog is open
+        let Some(key) = transactions.lock().unwrap().get(index).map(Transaction::key) else {
+            return;
+        };
+        let c = ctx.clone();
+        execute(async move {
+            let Some(file) = AsyncFileDialog::new()
+                .add_filter("receipt", RECEIPT_EXTENSIONS)
+                .pick_file()
+                .await else {
+                return;
+            };
+            let data = file.read().await;
+            let attachment = Attachment::new(file.file_name(), &data);
+            if let Err(e) = attachment::save(&attachment, &data).await {
+                show_error(e.to_string()).await;
+                return;
+            }
+            if let Some(transaction) = transactions.lock().unwrap().iter_mut().find(|t| t.key() == key) {
+                if !transaction.attachments.contains(&attachment) {
+                    transaction.attachments.push(attachment);
+                }
+            }
+            c.request_repaint();
+        });
+    }
+
+    fn save_receipt(attachment: Attachment) {
+        execute(async move {
+            match attachment::load(&attachment).await {
+                Ok(data) => save_as(attachment.file_name, data).await,
+                Err(e) => show_error(e.to_string()).await,
+            }
+        });
+    }
+
+    fn open_bill(&mut self, ctx: &egui::Context) {
+        //https://users.rust-lang.org/t/how-can-i-read-a-file-from-disk-by-filedialog-on-wasm/97868/2
+        if self.parse_job.is_none() {
+            self.parse_job = Some(ParseJob::pick(ctx, self.reader_options()));
+        }
+    }
+
+    /// Imported profiles first, the built-in Citibank profile takes whatever they do not.
+    fn reader_options(&self) -> ReaderOptions {
+        let mut options = ReaderOptions::default();
+        options.profiles.splice(0..0, self.statement_profiles.iter().cloned());
+        options
+    }
+
+    /// Picks a JSON profile file off the UI thread, the profiles window takes it from
+    /// `imported_profiles`.
+    fn import_profiles(&mut self, ctx: &egui::Context) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.imported_profiles = Some(receiver);
+        let c = ctx.clone();
+        execute(async move {
+            let Some(file) = AsyncFileDialog::new()
+                .add_filter("json", &["json"])
+                .pick_file()
+                .await else {
+                return;
+            };
+            let data = file.read().await;
+            match StatementProfile::from_json(&String::from_utf8_lossy(&data)) {
+                Ok(profiles) => {
+                    let _ = sender.send(profiles);
+                    c.request_repaint();
+                }
+                Err(e) => show_error(format!("{}: {}", file.file_name(), e)).await,
+            }
+        });
+    }
+
+    fn check_profile(&mut self) {
+        let options = self.reader_options();
+        let check = &mut self.profile_check;
+        let Some(profile) = options.profiles.get(check.profile) else {
+            check.result = None;
+            return;
+        };
+        check.result = Some(profile.compile().map(|profile| {
+            let pages = PlainTextExtractor::pages(&check.sample);
+            let first_page = pages.first()
+                .map(|lines| lines.iter().map(|l| l.text.clone()).collect::<Vec<String>>())
+                .unwrap_or_default();
+            let lines = pages.iter().flatten()
+                .map(|l| (l.text.clone(), profile.line_kind(&l.text)))
+                .collect();
+            let statement = StatementParser::new(options.clone()).parse_with(&profile, &pages);
+            CheckResult { detected: profile.detects(&first_page), lines, statement }
+        }));
+    }
+
+    /// Imported statement profiles, and a box to try one on text copied from a statement.
+    fn build_profiles_window(&mut self, ctx: &egui::Context) {
+        if let Some(profiles) = self.imported_profiles.as_ref().and_then(|r| r.try_recv().ok()) {
+            for profile in profiles {
+                //importing a profile again replaces the old version
+                self.statement_profiles.retain(|p| p.name != profile.name);
+                self.statement_profiles.push(profile);
+            }
+            self.imported_profiles = None;
+            self.profile_check.result = None;
+        }
+
+        let mut open = self.show_profiles;
+        let mut import = false;
+        let mut changed = self.profile_check.result.is_none();
+        Window::new("Statement profiles")
+            .open(&mut open)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                ui.label("A statement is read with the first profile whose fingerprints are on its first page. The built-in Citibank profile comes last and takes every statement.");
+                let mut remove = None;
+                egui::Grid::new("statement_profiles_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Name");
+                        ui.strong("Fingerprints");
+                        ui.end_row();
+                        for (index, profile) in self.statement_profiles.iter().enumerate() {
+                            ui.label(&profile.name);
+                            ui.label(profile.fingerprints.join(", "));
+                            if ui.button("Remove").clicked() {
+                                remove = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                        ui.label(StatementProfile::citibank().name);
+                        ui.weak("Built in");
+                        ui.end_row();
+                    });
+                if let Some(index) = remove {
+                    self.statement_profiles.remove(index);
+                    changed = true;
                 }
-                Some(s) => {
-                    transaction.tags.remove(s);
+                import = ui.button("Import JSON...").clicked();
+                ui.separator();
+
+                ui.strong("Check");
+                let profiles = self.reader_options().profiles;
+                let check = &mut self.profile_check;
+                egui::ComboBox::from_label("Profile")
+                    .selected_text(profiles.get(check.profile).map(|p| p.name.as_str()).unwrap_or_default())
+                    .show_ui(ui, |ui| {
+                        for (index, profile) in profiles.iter().enumerate() {
+                            changed |= ui.selectable_value(&mut check.profile, index, &profile.name).changed();
+                        }
+                    });
+                changed |= ui.add(TextEdit::multiline(&mut check.sample)
+                    .code_editor()
+                    .desired_rows(6)
+                    .desired_width(f32::INFINITY)
+                    .hint_text("Paste the text of a statement page, the statement inspector shows it")).changed();
+
+                match &check.result {
+                    Some(Ok(CheckResult { detected, lines, statement })) => {
+                        if !detected {
+                            ui.colored_label(ui.visuals().error_fg_color, "None of the fingerprints are in the text");
+                        }
+                        ui.label(format!("{} cards, {} transactions, {} unmatched lines",
+                            statement.cards.len(), statement.transactions.len(), statement.unmatched_lines.len()));
+                        inspector::build_legend(ui);
+                        egui::ScrollArea::vertical()
+                            .id_source("profile_check_lines")
+                            .max_height(200.0)
+                            .show(ui, |ui| {
+                                for (number, (line, kind)) in lines.iter().enumerate() {
+                                    ui.label(inspector::line_text(number + 1, line, *kind));
+                                }
+                            });
+                        egui::CollapsingHeader::new("Transactions")
+                            .show(ui, |ui| {
+                                egui::Grid::new("profile_check_grid").striped(true).show(ui, |ui| {
+                                    for transaction in &statement.transactions {
+                                        ui.label(&transaction.date);
+                                        ui.label(&transaction.description);
+                                        ui.label(format!("{:.2}", transaction.amount));
+                                        ui.label(&transaction.card);
+                                        ui.end_row();
+                                    }
+                                });
+                            });
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+                    None => {}
                 }
+            });
+        self.show_profiles = open;
+
+        if changed {
+            self.check_profile();
+        }
+        if import {
+            self.import_profiles(ctx);
+        }
+    }
+
+    /// Asks for a PDF statement and shows it in the inspector.
+    fn inspect_statement(&mut self, ctx: &egui::Context) {
+        let picked = self.inspector.pick(self.reader_options());
+        let c = ctx.clone();
+        execute(async move {
+            let Some(file) = AsyncFileDialog::new()
+                .add_filter("PDF statement", &["pdf"])
+                .pick_file()
+                .await else {
+                return;
+            };
+            *picked.lock().unwrap() = Some((file.file_name(), file.read().await));
+            c.request_repaint();
+        });
+    }
+
+    fn build_inspector_window(&mut self, ctx: &egui::Context) {
+        match self.inspector.show(ctx) {
+            Some(inspector::Request::OpenFile) => self.inspect_statement(ctx),
+            Some(inspector::Request::SaveSample(file_name, text)) => execute(save_as(file_name, text.into_bytes())),
+            None => {}
+        }
+    }
+
+    /// Imports files dropped on the window, `bytes` is set on the web and `path` on native.
+    fn import_dropped_files(&mut self, ctx: &egui::Context, dropped: Vec<egui::DroppedFile>) {
+        if self.parse_job.is_some() {
+            return;
+        }
+        let mut files = Vec::new();
+        for file in dropped {
+            let file_name = match &file.path {
+                Some(path) if file.name.is_empty() => path.file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                _ => file.name.clone(),
+            };
+            let data = match (file.bytes, &file.path) {
+                (Some(bytes), _) => Ok(bytes.to_vec()),
+                (None, Some(path)) => std::fs::read(path).map_err(|e| e.to_string()),
+                (None, None) => Err("The file could not be read".to_string()),
+            };
+            files.push((file_name, data));
+        }
+        self.parse_job = Some(ParseJob::read(ctx, self.reader_options(), files));
+    }
+
+    /// Stores the statement of every file which could be read in the history and opens the last
+    /// one. Files of the same statement, such as the CSV and the PDF of one month, are merged. A
+    /// statement the history already has is only replaced once the user agrees.
+    fn import_files(&mut self, statements: Vec<Statement>, results: Vec<ImportResult>) {
+        self.import_results = results;
+        let normaliser = MerchantNormaliser::new(&self.merchant_aliases);
+        let statement_currency = Some(self.currency_settings.statement.trim().to_uppercase())
+            .filter(|c| !c.is_empty())
+            .unwrap_or_else(|| self.currency_settings.home.clone());
+
+        let mut imports = Vec::<(Option<StatementKey>, Vec<Transaction>)>::new();
+        for mut statement in statements.into_iter().filter(|s| !s.transactions.is_empty()) {
+            normaliser.apply(&mut statement.transactions);
+            for transaction in &mut statement.transactions {
+                transaction.currency = statement_currency.clone();
+            }
+            let key = StatementKey::new(&statement.transactions, &statement.profile, statement.year);
+            match imports.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, transactions)) => transactions.extend(statement.transactions),
+                None => imports.push((key, statement.transactions)),
+            }
+        }
+        //without a month a statement cannot be kept in the history, only opened
+        imports.sort_by_key(|(key, _)| key.is_none());
+
+        self.sync_history();
+        self.pending_imports = imports;
+        self.continue_import();
+    }
+
+    /// Applies the pending imports until one would replace a statement of the history, which
+    /// waits for the replace window.
+    fn continue_import(&mut self) {
+        while let Some((key, _)) = self.pending_imports.first() {
+            if key.as_ref().is_some_and(|k| self.history.get(k).is_some()) {
+                return;
+            }
+            let (key, transactions) = self.pending_imports.remove(0);
+            self.apply_import(key, transactions);
+        }
+    }
+
+    /// The last import is opened, the ones before it go to the history.
+    fn apply_import(&mut self, key: Option<StatementKey>, transactions: Vec<Transaction>) {
+        match key {
+            Some(key) if !self.pending_imports.is_empty() => self.history.store(&key, &transactions),
+            key => self.open_imported(key, transactions),
+        }
+    }
+
+    fn open_imported(&mut self, key: Option<StatementKey>, transactions: Vec<Transaction>) {
+        self.selected = None;
+        self.focused = None;
+        *self.transactions.lock().unwrap() = transactions;
+        self.set_current_statement(key);
+        self.search.clear();
+        self.warn_over_budget();
+        self.sync_history();
+    }
+
+    /// Asks before an import replaces a statement kept in the history.
+    fn build_replace_statement_window(&mut self, ctx: &egui::Context) {
+        let Some((Some(key), _)) = self.pending_imports.first() else {
+            return;
+        };
+        let mut replace = None;
+        Window::new("Replace statement")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("The history already has the {} statement. Replace it, with its tags and notes, by the imported one?", key.label()));
+                ui.horizontal(|ui| {
+                    if ui.button("Replace").clicked() {
+                        replace = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        replace = Some(false);
+                    }
+                });
+            });
+        let Some(replace) = replace else {
+            return;
+        };
+        let (key, transactions) = self.pending_imports.remove(0);
+        if replace {
+            self.apply_import(key, transactions);
+        }
+        self.continue_import();
+    }
+
+    /// Progress of the statements being read, the outcome is applied once the job is over.
+    fn build_parse_job_window(&mut self, ctx: &egui::Context) {
+        let Some(job) = &mut self.parse_job else {
+            return;
+        };
+        match job.poll() {
+            Some(Outcome::Finished(statements, results)) => {
+                self.parse_job = None;
+                self.import_files(statements, results);
+                return;
             }
+            Some(Outcome::Cancelled) => {
+                self.parse_job = None;
+                return;
+            }
+            None => {}
+        }
+
+        //nothing to show while the file dialog is open
+        let Some(progress) = &job.progress else {
+            return;
         };
+        Window::new("Reading statements")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("File {} of {}: {}", progress.file, progress.files, progress.file_name));
+                let fraction = if progress.pages == 0 { 0.0 } else { progress.page as f32 / progress.pages as f32 };
+                ui.add(egui::ProgressBar::new(fraction)
+                    .desired_width(300.0)
+                    .text(format!("Page {} of {}", progress.page, progress.pages)));
+                if job.is_cancelling() {
+                    ui.label("Cancelling...");
+                } else if ui.button("Cancel").clicked() {
+                    job.cancel();
+                }
+            });
     }
 
-    fn build_table(&mut self, ui: &mut egui::Ui) {
+    /// Per-file counts and errors, shown after a batch import or when a file failed.
+    fn build_import_results_window(&mut self, ctx: &egui::Context) {
+        if self.import_results.len() < 2 && self.import_results.iter().all(|r| r.error.is_none()) {
+            return;
+        }
+        let mut close = false;
+        Window::new("Import results")
+            .collapsible(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("import_results_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("File");
+                        ui.strong("Transactions");
+                        ui.strong("Result");
+                        ui.end_row();
+                        for result in &self.import_results {
+                            ui.label(&result.file_name);
+                            ui.label(result.count.to_string());
+                            match &result.error {
+                                None => ui.label("Imported"),
+                                Some(error) => ui.colored_label(ui.visuals().error_fg_color, error),
+                            };
+                            ui.end_row();
+                        }
+                    });
+                if self.import_results.iter().any(|r| r.error.is_some()) {
+                    ui.label("File > Inspect statement shows the text which was read, and saves an anonymised sample to report a statement which cannot be read.");
+                }
+                let total = self.import_results.iter().map(|r| r.count).sum::<usize>();
+                ui.label(format!("{} transactions imported", total));
+                close = ui.button("OK").clicked();
+            });
+        if close {
+            self.import_results.clear();
+        }
+    }
+
+    fn export_csv(&self) {
+        let transactions = self.converted_transactions(&self.currency_settings.home);
+        let data = export::to_csv(&transactions, &self.split_settings).into_bytes();
+        execute(save_as("statement.csv".to_string(), data));
+    }
+
+    fn export_xlsx(&self) {
+        let transactions = self.converted_transactions(&self.currency_settings.home);
+        match export::to_xlsx(&transactions, &self.split_settings) {
+            Ok(data) => execute(save_as("statement.xlsx".to_string(), data)),
+            Err(e) => execute(show_error(e.to_string())),
+        }
+    }
+
+    /// The open statement in `currency`, for the exports, summaries, reports and journals. The
+    /// transactions without an exchange rate are left out with a warning.
+    fn converted_transactions(&self, currency: &str) -> Vec<Transaction> {
+        let month = self.current_statement.as_ref().map(|s| s.month.as_str());
+        let (converted, missing) = self.currency_settings.convert(&self.transactions.lock().unwrap(), currency, month);
+        if missing > 0 {
+            execute(show_warning(format!("{} transactions have no exchange rate to {} and are left out, import one in Settings.", missing, currency)));
+        }
+        converted
+    }
+
+    fn export_journal(&self, format: LedgerFormat) {
+        let transactions = self.converted_transactions(&self.ledger_settings.currency);
+        match ledger::to_journal(&transactions, &self.split_settings, &self.ledger_settings, format) {
+            Ok(journal) => execute(save_as(format.file_name().to_string(), journal.into_bytes())),
+            Err(e) => execute(show_error(e.to_string())),
+        }
+    }
+
+    fn copy_summary(&self, ctx: &egui::Context) {
+        let transactions = self.converted_transactions(&self.currency_settings.home);
+        ctx.copy_text(share::summary_text(&transactions, &self.split_settings));
+    }
+
+    fn copy_snapshot_link(&self, ctx: &egui::Context) {
+        let Some(base_url) = self.share_base_url() else {
+            execute(show_error("Set the web app address in Settings to share a snapshot link.".to_string()));
+            return;
+        };
+        let transactions = self.converted_transactions(&self.currency_settings.home);
+        match Snapshot::new(&transactions, &self.split_settings, self.share_notes).url(&base_url) {
+            Ok(url) => ctx.copy_text(url),
+            Err(e) => execute(show_error(e.to_string())),
+        }
+    }
+
+    fn generate_report(&self) {
+        let transactions = self.converted_transactions(&self.currency_settings.home);
+        let html = report::to_html(&transactions, &self.split_settings);
+        execute(save_as("report.html".to_string(), html.into_bytes()));
+    }
+
+    fn build_account_mappings(ui: &mut egui::Ui, id: &str, key: &str, mappings: &mut Vec<AccountMapping>) {
+        let mut remove = None;
+        egui::Grid::new(id)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong(key);
+                ui.strong("Account");
+                ui.end_row();
+                for (index, mapping) in mappings.iter_mut().enumerate() {
+                    ui.text_edit_singleline(&mut mapping.key);
+                    ui.text_edit_singleline(&mut mapping.account);
+                    if ui.button("Remove").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(index) = remove {
+            mappings.remove(index);
+        }
+        if ui.button("Add mapping").clicked() {
+            mappings.push(AccountMapping::default());
+        }
+    }
+
+    fn build_format_rules(ui: &mut egui::Ui, rules: &mut Vec<FormatRule>) {
+        let mut remove = None;
+        egui::Grid::new("format_rules_grid")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("");
+                ui.strong("Name");
+                ui.strong("Condition");
+                ui.strong("Text");
+                ui.strong("Background");
+                ui.strong("Bold");
+                ui.strong("Dim");
+                ui.end_row();
+                for (index, rule) in rules.iter_mut().enumerate() {
+                    ui.checkbox(&mut rule.enabled, "");
+                    ui.add(TextEdit::singleline(&mut rule.name).desired_width(100.0));
+                    let response = ui.add(TextEdit::singleline(&mut rule.condition).desired_width(140.0));
+                    if let Err(e) = Query::parse(&rule.condition) {
+                        response.on_hover_text(RichText::new(e).color(ui.visuals().error_fg_color));
+                    }
+                    for color in [&mut rule.text_color, &mut rule.background] {
+                        ui.horizontal(|ui| {
+                            let mut enabled = color.is_some();
+                            if ui.checkbox(&mut enabled, "").changed() {
+                                *color = enabled.then_some(ui.visuals().text_color());
+                            }
+                            if let Some(color) = color {
+                                ui.color_edit_button_srgba(color);
+                            }
+                        });
+                    }
+                    ui.checkbox(&mut rule.bold, "");
+                    ui.checkbox(&mut rule.dim, "");
+                    if ui.button("Remove").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(index) = remove {
+            rules.remove(index);
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Add rule").clicked() {
+                rules.push(FormatRule::default());
+            }
+            if ui.button("Reset to defaults").clicked() {
+                *rules = FormatRule::defaults();
+            }
+        });
+    }
+
+    /// Picks a rate table CSV off the UI thread, the settings window takes it from `imported_rates`.
+    fn import_rates(&mut self, ctx: &egui::Context) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.imported_rates = Some(receiver);
+        let c = ctx.clone();
+        execute(async move {
+            let Some(file) = AsyncFileDialog::new()
+                .add_filter("csv", &["csv"])
+                .pick_file()
+                .await else {
+                return;
+            };
+            let data = file.read().await;
+            match RateTable::from_csv(&String::from_utf8_lossy(&data)) {
+                Ok(rates) => {
+                    let _ = sender.send(rates);
+                    c.request_repaint();
+                }
+                Err(e) => show_error(e.to_string()).await,
+            }
+        });
+    }
+
+    fn build_settings_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_settings;
+        let mut import_rates = false;
+        Window::new("Settings")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                egui::CollapsingHeader::new("People")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        ui.label("Personal transactions belong to the first person, joint transactions are split evenly.");
+                        let people = &mut self.split_settings.people;
+                        let mut remove = None;
+                        for (index, person) in people.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.text_edit_singleline(person);
+                                if ui.button("Remove").clicked() {
+                                    remove = Some(index);
+                                }
+                            });
+                        }
+                        if let Some(index) = remove {
+                            people.remove(index);
+                        }
+                        if ui.button("Add person").clicked() {
+                            people.push(format!("Person {}", people.len() + 1));
+                        }
+                    });
+                egui::CollapsingHeader::new("Currency")
+                    .show(ui, |ui| {
+                        let settings = &mut self.currency_settings;
+                        egui::Grid::new("currency_settings_grid")
+                            .num_columns(2)
+                            .show(ui, |ui| {
+                                ui.label("Home currency");
+                                ui.text_edit_singleline(&mut settings.home)
+                                    .on_hover_text("Totals are converted to this currency");
+                                ui.end_row();
+                                ui.label("Statement currency");
+                                ui.add(TextEdit::singleline(&mut settings.statement)
+                                    .hint_text(settings.home.as_str()))
+                                    .on_hover_text("Currency of the statements opened from now on");
+                                ui.end_row();
+                            });
+                        ui.label("Exchange rates are read from a local CSV file with from,to,rate[,date] lines, for example USD,SGD,1.35,2024-06-01.");
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{} rates loaded", settings.rates.rates.len()));
+                            import_rates = ui.button("Import rates...").clicked();
+                            if ui.button("Clear").clicked() {
+                                settings.rates = RateTable::default();
+                            }
+                        });
+                    });
+                egui::CollapsingHeader::new("Accounts")
+                    .show(ui, |ui| {
+                        ui.label("Used by the Ledger, hledger and Beancount exports. Other people's share of a transaction is posted to the receivable account.");
+                        let settings = &mut self.ledger_settings;
+                        egui::Grid::new("ledger_settings_grid")
+                            .num_columns(2)
+                            .show(ui, |ui| {
+                                ui.label("Statement year");
+                                ui.add(egui::DragValue::new(&mut settings.year));
+                                ui.end_row();
+                                ui.label("Currency");
+                                ui.text_edit_singleline(&mut settings.currency)
+                                    .on_hover_text("Amounts are converted to this currency with the exchange rates");
+                                ui.end_row();
+                                ui.label("Default expense account");
+                                ui.text_edit_singleline(&mut settings.expense_account);
+                                ui.end_row();
+                                ui.label("Default card account");
+                                ui.text_edit_singleline(&mut settings.card_account);
+                                ui.end_row();
+                                ui.label("Receivable account");
+                                ui.text_edit_singleline(&mut settings.receivable_account);
+                                ui.end_row();
+                            });
+                        ui.separator();
+                        Self::build_account_mappings(ui, "tag_accounts_grid", "Tag", &mut settings.tag_accounts);
+                        ui.separator();
+                        Self::build_account_mappings(ui, "card_accounts_grid", "Card ending", &mut settings.card_accounts);
+                    });
+                egui::CollapsingHeader::new("Row formatting")
+                    .show(ui, |ui| {
+                        ui.label("Conditions use the search box syntax, for example amount<0 or tag:none. Later rules win on colours.");
+                        Self::build_format_rules(ui, &mut self.format_rules);
+                    });
+                egui::CollapsingHeader::new("Sharing")
+                    .show(ui, |ui| {
+                        ui.label("Address of the web app, snapshot links open there. The web app uses its own address.");
+                        ui.add(TextEdit::singleline(&mut self.share_url)
+                            .hint_text("https://example.com/billsplit/"));
+                        ui.checkbox(&mut self.share_notes, "Include notes in snapshot links")
+                            .on_hover_text("Links end up in browser history and chat previews, cards only show their last digits");
+                    });
+            });
+        self.show_settings = open;
+
+        if let Some(rates) = self.imported_rates.as_ref().and_then(|r| r.try_recv().ok()) {
+            self.currency_settings.rates = rates;
+            self.imported_rates = None;
+        }
+        if import_rates {
+            self.import_rates(ctx);
+        }
+    }
+
+    fn run_command(&mut self, ctx: &egui::Context, command: Command) {
+        match command {
+            Command::OpenBill => self.open_bill(ctx),
+            Command::ExportCsv => self.export_csv(),
+            Command::ExportXlsx => self.export_xlsx(),
+            Command::ExportJournal(format) => self.export_journal(format),
+            Command::GenerateReport => self.generate_report(),
+            Command::CopySummary => self.copy_summary(ctx),
+            Command::CopySnapshotLink => self.copy_snapshot_link(ctx),
+            Command::ApplyMerchantAliases => self.normalise_merchants(),
+            Command::ShowStatement => self.show_dashboard = false,
+            Command::ShowDashboard => {
+                self.sync_history();
+                self.show_dashboard = true;
+            }
+            Command::Settings => self.show_settings = true,
+            Command::MerchantAliases => self.show_merchant_aliases = true,
+            Command::Budgets => self.show_budgets = true,
+            Command::KeyboardShortcuts => self.show_shortcuts = true,
+            Command::StatementViewer => self.show_statement_viewer = !self.show_statement_viewer,
+            Command::InspectStatement => self.inspect_statement(ctx),
+            Command::StatementProfiles => self.show_profiles = true,
+        }
+    }
+
+    /// Moves the focused row, tags it and marks it as reviewed. `rows` is the table order.
+    fn handle_row_keys(&mut self, ctx: &egui::Context, actions: &[Action], tags: &[String], rows: &[usize]) {
+        let position = rows.iter().position(|index| Some(*index) == self.focused);
+        for action in actions {
+            match action {
+                Action::NextRow => {
+                    let next = position.map_or(0, |p| (p + 1).min(rows.len().saturating_sub(1)));
+                    self.focused = rows.get(next).copied();
+                    self.scroll_to_focus = true;
+                }
+                Action::PreviousRow => {
+                    let previous = position.map_or(0, |p| p.saturating_sub(1));
+                    self.focused = rows.get(previous).copied();
+                    self.scroll_to_focus = true;
+                }
+                Action::OpenDetails => self.selected = self.focused,
+                Action::Search => {
+                    //the "/" would otherwise be typed into the search box it just focused
+                    ctx.input_mut(|i| i.events.retain(|e| !matches!(e, egui::Event::Text(t) if t == "/")));
+                    ctx.memory_mut(|m| m.request_focus(egui::Id::new("search")));
+                }
+                _ => {}
+            }
+        }
+
+        let Some(index) = self.focused.filter(|_| !self.read_only) else {
+            return;
+        };
+        let mut t = self.transactions.lock().unwrap();
+        let Some(transaction) = t.get_mut(index) else {
+            return;
+        };
+        if actions.contains(&Action::ToggleReviewed) {
+            transaction.reviewed = !transaction.reviewed;
+        }
+        for tag in tags {
+            Self::toggle_tag(transaction, tag);
+        }
+    }
+
+    fn build_shortcut_button(ui: &mut egui::Ui, rebinding: &mut Option<Binding>, binding: Binding, shortcut: &egui::KeyboardShortcut) {
+        let text = if *rebinding == Some(binding) {
+            "Press a key...".to_string()
+        } else {
+            ui.ctx().format_shortcut(shortcut)
+        };
+        if ui.button(text).on_hover_text("Change, Escape cancels").clicked() {
+            *rebinding = Some(binding);
+        }
+    }
+
+    fn build_shortcuts_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_shortcuts;
+        Window::new("Keyboard shortcuts")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label("Row keys work while no text box has the keyboard, click a shortcut to change it.");
+                egui::Grid::new("shortcuts_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for action in Action::ALL {
+                            ui.label(action.name());
+                            let shortcut = self.shortcuts.get(action);
+                            Self::build_shortcut_button(ui, &mut self.rebinding, Binding::Action(action), &shortcut);
+                            ui.end_row();
+                        }
+                    });
+                ui.separator();
+                ui.strong("Tags");
+                ui.label("Toggles the tag on the focused row.");
+                let mut remove = None;
+                egui::Grid::new("tag_shortcuts_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (index, tag) in self.shortcuts.tags.iter_mut().enumerate() {
+                            ui.add(TextEdit::singleline(&mut tag.tag).desired_width(100.0));
+                            Self::build_shortcut_button(ui, &mut self.rebinding, Binding::Tag(index), &tag.shortcut);
+                            if ui.button("Remove").clicked() {
+                                remove = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(index) = remove {
+                    self.shortcuts.tags.remove(index);
+                    self.rebinding = None;
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Add tag shortcut").clicked() {
+                        let key = egui::Key::from_name(&(self.shortcuts.tags.len() + 1).to_string())
+                            .unwrap_or(egui::Key::Num0);
+                        self.shortcuts.tags.push(TagShortcut {
+                            tag: String::new(),
+                            shortcut: egui::KeyboardShortcut::new(egui::Modifiers::NONE, key),
+                        });
+                    }
+                    if ui.button("Reset to defaults").clicked() {
+                        self.shortcuts = Shortcuts::default();
+                        self.rebinding = None;
+                    }
+                });
+            });
+        self.show_shortcuts = open;
+        if !open {
+            self.rebinding = None;
+        }
+    }
+
+    fn build_details_panel(&mut self, ctx: &egui::Context) {
+        let Some(index) = self.selected else {
+            return;
+        };
+        let transactions = Arc::clone(&self.transactions);
+        let mut t = transactions.lock().unwrap();
+        let Some(transaction) = t.get_mut(index) else {
+            self.selected = None;
+            return;
+        };
+
+        let mut close = false;
+        let mut attach = false;
+        let mut removed = None;
+        egui::SidePanel::right("details_panel")
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("Details");
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        close = ui.button("Close").clicked();
+                    });
+                });
+                ui.separator();
+                egui::Grid::new("details_grid")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.strong("Date");
+                        ui.label(&transaction.date);
+                        ui.end_row();
+                        ui.strong("Description");
+                        ui.label(&transaction.description);
+                        ui.end_row();
+                        ui.strong("Merchant");
+                        ui.label(transaction.merchant.display());
+                        ui.end_row();
+                        ui.strong("Amount");
+                        ui.label(format!("{:.2}", transaction.amount));
+                        ui.end_row();
+                        ui.strong("Card");
+                        ui.label(&transaction.card);
+                        ui.end_row();
+                        ui.strong("Tags");
+                        ui.label(transaction.tags.join(", "));
+                        ui.end_row();
+                        ui.strong("Reviewed");
+                        ui.add_enabled(!self.read_only, egui::Checkbox::without_text(&mut transaction.reviewed));
+                        ui.end_row();
+                    });
+                ui.separator();
+                ui.strong("Notes");
+                if self.read_only {
+                    ui.label(&transaction.notes);
+                    return;
+                }
+                ui.add(TextEdit::multiline(&mut transaction.notes)
+                    .hint_text("Dinner with Mum, she paid half in cash")
+                    .desired_width(f32::INFINITY));
+                ui.separator();
+                ui.strong("Receipts");
+                for (attachment_index, attachment) in transaction.attachments.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(&attachment.file_name);
+                        if ui.button("Save as...").clicked() {
+                            Self::save_receipt(attachment.clone());
+                        }
+                        if ui.button("Remove").clicked() {
+                            removed = Some(attachment_index);
+                        }
+                    });
+                }
+                attach = ui.button("Attach receipt...").clicked();
+            });
+
+        if let Some(attachment_index) = removed {
+            let attachment = transaction.attachments.remove(attachment_index);
+            //the store is content addressed, keep the file while another transaction still uses it
+            if !t.iter().any(|t| t.attachments.contains(&attachment))
+                && !self.history.uses_attachment(&attachment, self.current_statement.as_ref()) {
+                execute(async move {
+                    if let Err(e) = attachment::remove(&attachment).await {
+                        show_error(e.to_string()).await;
+                    }
+                });
+            }
+        }
+        drop(t);
+        if attach {
+            self.attach_receipt(ctx, index);
+        }
+        if close {
+            self.selected = None;
+        }
+    }
+
+    /// Statement pages next to the table, with the line of the selected row highlighted.
+    fn build_statement_panel(&mut self, ctx: &egui::Context) {
+        if !self.show_statement_viewer {
+            return;
+        }
+        let source = self.selected.or(self.focused)
+            .and_then(|index| self.transactions.lock().unwrap().get(index).and_then(|t| t.source.clone()));
+
+        let mut close = false;
+        egui::SidePanel::left("statement_panel")
+            .resizable(true)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("Statement");
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        close = ui.button("Close").clicked();
+                    });
+                });
+                ui.separator();
+                let Some(source) = source else {
+                    ui.label("Select a row to see the statement line it was read from.");
+                    return;
+                };
+                let Some(statement) = &source.statement else {
+                    ui.label("This row was not read from a PDF statement, or the statement was opened before it was kept.");
+                    return;
+                };
+                if self.viewer.needs(statement) {
+                    let loaded = self.viewer.load(statement.clone());
+                    let statement = statement.clone();
+                    let c = ctx.clone();
+                    execute(async move {
+                        let data = attachment::load(&statement).await.map_err(|e| e.to_string());
+                        *loaded.lock().unwrap() = Some(data);
+                        c.request_repaint();
+                    });
+                }
+                ui.label(format!("{}, page {}", statement.file_name, source.page + 1));
+                self.viewer.show(ui, &source);
+            });
+        if close {
+            self.show_statement_viewer = false;
+        }
+    }
+
+    /// Rows of the table after the search and the sort, as indices into `transactions`. An invalid
+    /// search filters nothing and comes back as the error to show under the search box.
+    fn visible_rows(&self, transactions: &[Transaction]) -> (Vec<usize>, Option<String>) {
+        let (query, error) = match Query::parse(&self.search) {
+            Ok(query) => (query, None),
+            Err(e) => (Query::default(), Some(e)),
+        };
+        let month = self.current_statement.as_ref().map(|s| s.month.as_str());
+        let mut rows = (0..transactions.len())
+            .filter(|index| {
+                let transaction = &transactions[*index];
+                query.matches_amount(transaction, self.currency_settings.shown_amount(transaction, month), month)
+            })
+            .collect::<Vec<usize>>();
+        if let Some(sort) = &self.sort {
+            sort.apply(&mut rows, transactions, month);
+        }
+        (rows, error)
+    }
+
+    fn build_search_bar(&mut self, ui: &mut egui::Ui, error: Option<String>) {
+        ui.horizontal(|ui| {
+            ui.label("Search: ");
+            ui.add(TextEdit::singleline(&mut self.search)
+                .id(egui::Id::new("search"))
+                .hint_text("amount>50 tag:joint card:5136 desc:grab before:2024-06-10")
+                .desired_width(360.0));
+            if !self.search.is_empty() && ui.button("Clear").clicked() {
+                self.search.clear();
+            }
+
+            let mut apply = None;
+            let mut remove = None;
+            ui.menu_button("Views", |ui| {
+                for (index, view) in self.saved_views.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.button(&view.name).on_hover_text(&view.search).clicked() {
+                            apply = Some(index);
+                            ui.close_menu();
+                        }
+                        if ui.small_button("🗑").on_hover_text("Delete view").clicked() {
+                            remove = Some(index);
+                        }
+                    });
+                }
+                if !self.saved_views.is_empty() {
+                    ui.separator();
+                }
+                ui.horizontal(|ui| {
+                    ui.add(TextEdit::singleline(&mut self.view_name)
+                        .hint_text("View name")
+                        .desired_width(120.0));
+                    if ui.add_enabled(!self.view_name.trim().is_empty(), Button::new("Save view")).clicked() {
+                        self.saved_views.push(SavedView {
+                            name: self.view_name.trim().to_string(),
+                            search: self.search.clone(),
+                            sort: self.sort,
+                        });
+                        self.view_name.clear();
+                        ui.close_menu();
+                    }
+                });
+            });
+            if let Some(index) = apply {
+                let view = &self.saved_views[index];
+                self.search = view.search.clone();
+                self.sort = view.sort;
+            }
+            if let Some(index) = remove {
+                self.saved_views.remove(index);
+            }
+        });
+        if let Some(error) = error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    /// Header which cycles the sort of its column between ascending, descending and off.
+    fn build_sort_header(ui: &mut egui::Ui, sort: &mut Option<Sort>, label: &str, column: SortColumn) {
+        let arrow = match sort {
+            Some(s) if s.column == column && s.ascending => " ⬆",
+            Some(s) if s.column == column => " ⬇",
+            _ => "",
+        };
+        let header = Button::new(RichText::new(format!("{}{}", label, arrow)).strong()).frame(false);
+        if ui.add(header).on_hover_text("Sort").clicked() {
+            *sort = match sort {
+                Some(s) if s.column == column && s.ascending => Some(Sort { column, ascending: false }),
+                Some(s) if s.column == column => None,
+                _ => Some(Sort { column, ascending: true }),
+            };
+        }
+    }
+
+    /// Amount in the home currency, with the statement and charged amounts on hover when they differ.
+    fn build_amount(ui: &mut egui::Ui, settings: &CurrencySettings, statement_month: Option<&str>, format: &RowFormat, transaction: &Transaction) {
+        let currency = settings.currency_of(transaction);
+        let mut hover = Vec::new();
+        if currency != settings.home {
+            hover.push(format!("Statement amount: {} {:.2}", currency, transaction.amount));
+        }
+        if let Some(original) = &transaction.original {
+            hover.push(format!("Charged as {} {:.2}", original.currency, original.amount));
+        }
+
+        let response = match settings.home_amount(transaction, statement_month) {
+            Some(amount) => ui.label(format.text(format!("{:.2}", amount))),
+            None => {
+                hover.push(format!("No exchange rate from {} to {}, import one in Settings", currency, settings.home));
+                ui.label(format.text(format!("{} {:.2}", currency, transaction.amount))
+                    .color(ui.visuals().error_fg_color))
+            }
+        };
+        if let Some(original) = &transaction.original {
+            ui.label(RichText::new(&original.currency).small().weak());
+        }
+        if !hover.is_empty() {
+            response.on_hover_text(hover.join("\n"));
+        }
+    }
+
+    fn build_table(&mut self, ui: &mut egui::Ui, rows: &[usize]) {
         use egui_extras::{Column, TableBuilder};
 
         let available_height = ui.available_height();
@@ -71,20 +1547,28 @@ impl BillSplitApp {
             .max_scroll_height(0.0)
             .max_scroll_height(available_height)
             .sense(egui::Sense::click());
+        if std::mem::take(&mut self.scroll_to_focus) {
+            if let Some(position) = rows.iter().position(|index| Some(*index) == self.focused) {
+                table = table.scroll_to_row(position, None);
+            }
+        }
 
         table
             .header(20.0, |mut header| {
                 header.col(|ui|{
-                   ui.strong("Date");
+                    Self::build_sort_header(ui, &mut self.sort, "Date", SortColumn::Date);
                 });
                 header.col(|ui|{
-                    ui.strong("Description");
+                    Self::build_sort_header(ui, &mut self.sort, "Description", SortColumn::Description);
+                    ui.toggle_value(&mut self.show_clean_description, "Clean")
+                        .on_hover_text("Toggle between the raw statement text and the clean merchant name");
                 });
                 header.col(|ui|{
-                    ui.strong("Amount (SGD)");
+                    let label = format!("Amount ({})", self.currency_settings.home);
+                    Self::build_sort_header(ui, &mut self.sort, &label, SortColumn::Amount);
                 });
                 header.col(|ui|{
-                    ui.strong("Card");
+                    Self::build_sort_header(ui, &mut self.sort, "Card", SortColumn::Card);
                 });
                 header.col(|ui|{
                     ui.strong("Tags");
@@ -92,31 +1576,54 @@ impl BillSplitApp {
             })
             .body(|mut body|{
                 let mut t = self.transactions.lock().unwrap();
-                let mut t = &mut *t;
-                for mut transaction in t {
+                let t = &mut *t;
+                let formatter = Formatter::new(&self.format_rules);
+                for &index in rows {
+                    let transaction = &mut t[index];
+                    let month = self.current_statement.as_ref().map(|s| s.month.as_str());
+                    let format = formatter.format(transaction, self.currency_settings.shown_amount(transaction, month), month);
                     body.row(18.0, |mut row |{
+                        row.set_selected(self.selected == Some(index) || self.focused == Some(index));
                        row.col(|ui|{
-                          ui.label(&transaction.date);
+                          format.paint_background(ui);
+                          ui.label(format.text(&transaction.date));
                        });
                         row.col(|ui|{
-                            ui.label(&transaction.description);
+                            format.paint_background(ui);
+                            if self.show_clean_description && !transaction.merchant.name.is_empty() {
+                                ui.label(format.text(transaction.merchant.display()))
+                                    .on_hover_text(&transaction.description);
+                            } else {
+                                ui.label(format.text(&transaction.description));
+                            }
                         });
                         row.col(|ui|{
-                            if (*&transaction.amount/10f64).round() * 10f64 >= 50f64 {
-                                ui.label(RichText::new(format!("{:.2}", &transaction.amount))
-                                             .strong());
-                            }else {
-                                ui.label(format!("{:.2}", &transaction.amount));
-                            }
+                            format.paint_background(ui);
+                            Self::build_amount(ui, &self.currency_settings, self.current_statement.as_ref().map(|s| s.month.as_str()), &format, transaction);
                         });
                         row.col(|ui|{
-                            ui.label(&transaction.card);
+                            format.paint_background(ui);
+                            ui.label(format.text(&transaction.card));
                         });
                         row.col(|ui|{
-                            //ui.label(&transaction.tags.join(", "));
-                            Self::build_button(ui, transaction, PERSONAL_TAG);
-                            Self::build_button(ui, transaction, JOINT_TAG);
+                            format.paint_background(ui);
+                            if self.read_only {
+                                ui.label(transaction.tags.join(", "));
+                            } else {
+                                Self::build_button(ui, transaction, PERSONAL_TAG);
+                                Self::build_button(ui, transaction, JOINT_TAG);
+                            }
+                            if !transaction.notes.is_empty() || !transaction.attachments.is_empty() {
+                                ui.label("📎").on_hover_text(&transaction.notes);
+                            }
+                            if transaction.reviewed {
+                                ui.label("✔").on_hover_text("Reviewed");
+                            }
                         });
+                        if row.response().clicked() {
+                            self.selected = Some(index);
+                            self.focused = Some(index);
+                        }
                     });
                 }
             });
@@ -126,6 +1633,11 @@ impl BillSplitApp {
 impl eframe::App for BillSplitApp {
     /// Called by the framework to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        //a shared snapshot must not replace the visitor's own statement
+        if self.read_only {
+            return;
+        }
+        self.sync_history();
         eframe::set_value(storage, eframe::APP_KEY, self);
     }
 
@@ -135,6 +1647,46 @@ impl eframe::App for BillSplitApp {
 
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        //while a shortcut is being changed the next key press belongs to it
+        let (mut actions, mut tags) = (Vec::new(), Vec::new());
+        if let Some(binding) = self.rebinding {
+            if let Some(captured) = shortcuts::captured_shortcut(ctx) {
+                if let Some(shortcut) = captured {
+                    self.shortcuts.set(binding, shortcut);
+                }
+                self.rebinding = None;
+            }
+        } else {
+            let text_focused = ctx.wants_keyboard_input() || self.palette.open;
+            actions = self.shortcuts.pressed(ctx, text_focused);
+            if !text_focused {
+                tags = self.shortcuts.pressed_tags(ctx);
+            }
+        }
+        if actions.contains(&Action::CommandPalette) && !self.read_only {
+            self.palette.toggle();
+        }
+        if actions.contains(&Action::Help) {
+            self.show_shortcuts = !self.show_shortcuts;
+        }
+        if let Some(command) = self.palette.show(ctx) {
+            self.run_command(ctx, command);
+        }
+
+        if !self.read_only {
+            let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+            if !dropped.is_empty() {
+                self.import_dropped_files(ctx, dropped);
+            }
+            if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+                let screen = ctx.screen_rect();
+                let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("drop_files")));
+                painter.rect_filled(screen, 0.0, egui::Color32::from_black_alpha(192));
+                painter.text(screen.center(), egui::Align2::CENTER_CENTER, "Drop PDF or CSV statements to import",
+                    egui::FontId::proportional(24.0), egui::Color32::WHITE);
+            }
+        }
+
         // Put your widgets into a `SidePanel`, `TopBottomPanel`, `CentralPanel`, `Window` or `Area`.
         // For inspiration and more examples, go to https://emilk.github.io/egui
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
@@ -142,47 +1694,88 @@ impl eframe::App for BillSplitApp {
             egui::menu::bar(ui, |ui| {
                 egui::widgets::global_dark_light_mode_switch(ui);
                 ui.separator();
+                if self.read_only {
+                    ui.label(RichText::new("Shared snapshot (read-only)").strong());
+                    return;
+                }
                 ui.menu_button("File", |ui| {
                     if ui.button("Open bill...").clicked() {
-                        //https://users.rust-lang.org/t/how-can-i-read-a-file-from-disk-by-filedialog-on-wasm/97868/2
-                        let transactions = Arc::clone(&self.transactions);
-                        let c = ctx.clone();
-                        let future = async move {
-                            let file = AsyncFileDialog::new()
-                                .add_filter("pdf", &["pdf"])
-                                .set_directory("/")
-                                .pick_file()
-                                .await;
-                            match file {
-                                None => {}
-                                Some(f) => {
-                                    let bill_reader = CreditCardBillReader::default();
-                                    let data = f.read().await;
-                                    let transactions_results = bill_reader.read(data);
-                                    // log!(Level::Info, "Transactions count: {}", transactions.len());
-                                    // let mut total  = 0.0f64;
-                                    // for transaction in transactions {
-                                    //     log!(Level::Info, "{}", transaction);
-                                    //     total += transaction.amount;
-                                    // }
-                                    // log!(Level::Info, "Total: ${:.2}", total);
-                                    // for transaction in transactions {
-                                    //     self.transactions.push(transaction);
-                                    // }
-                                    let mut t = transactions.lock().unwrap();
-                                    t.clear();
-                                    for transaction in transactions_results {
-                                        t.push(transaction);
-                                    }
-                                    c.request_repaint();
-                                }
+                        self.open_bill(ctx);
+                        ui.close_menu();
+                    }
+                    ui.menu_button("Export", |ui| {
+                        if ui.button("CSV...").clicked() {
+                            self.export_csv();
+                            ui.close_menu();
+                        }
+                        if ui.button("Excel (XLSX)...").clicked() {
+                            self.export_xlsx();
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        for format in LedgerFormat::ALL {
+                            if ui.button(format!("{}...", format.name())).clicked() {
+                                self.export_journal(format);
+                                ui.close_menu();
                             }
-                        };
-
-                        async_std::task::block_on(future);
+                        }
+                    });
+                    ui.menu_button("Share summary", |ui| {
+                        if ui.button("Copy text").clicked() {
+                            self.copy_summary(ctx);
+                            ui.close_menu();
+                        }
+                        if ui.button("Copy snapshot link").clicked() {
+                            self.copy_snapshot_link(ctx);
+                            ui.close_menu();
+                        }
+                    });
+                    if ui.button("Generate report...").clicked() {
+                        self.generate_report();
+                        ui.close_menu();
+                    }
+                    if ui.button("Inspect statement...").clicked() {
+                        self.inspect_statement(ctx);
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Settings...").clicked() {
+                        self.show_settings = true;
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("View", |ui| {
+                    ui.checkbox(&mut self.show_clean_description, "Clean descriptions");
+                    ui.checkbox(&mut self.show_statement_viewer, "Statement viewer");
+                    if ui.button("Merchant aliases...").clicked() {
+                        self.show_merchant_aliases = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Budgets...").clicked() {
+                        self.show_budgets = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Statement profiles...").clicked() {
+                        self.show_profiles = true;
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    let shortcut = ctx.format_shortcut(&self.shortcuts.get(Action::CommandPalette));
+                    if ui.add(Button::new("Command palette").shortcut_text(shortcut)).clicked() {
+                        self.palette.toggle();
+                        ui.close_menu();
+                    }
+                    let shortcut = ctx.format_shortcut(&self.shortcuts.get(Action::Help));
+                    if ui.add(Button::new("Keyboard shortcuts...").shortcut_text(shortcut)).clicked() {
+                        self.show_shortcuts = true;
                         ui.close_menu();
                     }
                 });
+                ui.separator();
+                ui.selectable_value(&mut self.show_dashboard, false, "Statement");
+                if ui.selectable_value(&mut self.show_dashboard, true, "Dashboard").clicked() {
+                    self.sync_history();
+                }
             });
         });
 
@@ -202,53 +1795,111 @@ impl eframe::App for BillSplitApp {
                 });
             });
 
+        self.build_merchant_aliases_window(ctx);
+        self.build_settings_window(ctx);
+        self.build_budgets_window(ctx);
+        self.build_shortcuts_window(ctx);
+        self.build_parse_job_window(ctx);
+        self.build_replace_statement_window(ctx);
+        self.build_import_results_window(ctx);
+        self.build_inspector_window(ctx);
+        self.build_profiles_window(ctx);
+        self.build_details_panel(ctx);
+        self.build_statement_panel(ctx);
+
         egui::CentralPanel::default().show(ctx, |ui| {
+            if self.show_dashboard {
+                let selection = egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| dashboard::show(ui, &self.history, &self.currency_settings))
+                    .inner;
+                if let Some(selection) = selection {
+                    if let Some(key) = self.history.first_of_month(&selection.month).map(|s| s.key()) {
+                        self.open_statement(&key);
+                    }
+                    self.search = format!("tag:\"{}\"", selection.tag);
+                    self.show_dashboard = false;
+                }
+                return;
+            }
+
             egui::ScrollArea::both()
                 .auto_shrink([false, false])
                 .show(ui, |ui|{
+                    let (rows, error) = self.visible_rows(&self.transactions.lock().unwrap());
+                    self.handle_row_keys(ctx, &actions, &tags, &rows);
+                    self.build_search_bar(ui, error);
                     ui.horizontal(|ui|{
                         let t = self.transactions.lock().unwrap();
+                        //with a search the totals only cover the rows it matches
+                        let shown = rows.iter().map(|index| &t[*index]).collect::<Vec<&Transaction>>();
 
                         ui.label("Transactions: ");
-                        ui.label(RichText::new(format!("{}", t.len()))
+                        let count = if shown.len() == t.len() {
+                            format!("{}", t.len())
+                        } else {
+                            format!("{} of {}", shown.len(), t.len())
+                        };
+                        ui.label(RichText::new(count)
                             .strong()
                             .size(20.0));
                         ui.separator();
 
-                        let mut total = 0f64;
-                        for transaction in &*t {
-                            total += transaction.amount;
-                        }
+                        //totals are in the home currency, rows without an exchange rate are left out
+                        let home = &self.currency_settings.home;
+                        let home_amount = |t: &Transaction| self.currency_settings.home_amount(t, self.current_statement.as_ref().map(|s| s.month.as_str()));
+                        let total = shown.iter().filter_map(|t| home_amount(t)).sum::<f64>();
                         ui.label("Total: ");
-                        ui.label(RichText::new(format!("${:.2}", total))
+                        ui.label(RichText::new(format!("{} {:.2}", home, total))
                             .strong()
                             .size(20.0));
                         ui.separator();
 
-                        let mut personal_total = 0f64;
-                        for transaction in &*t.iter()
-                            .filter(|t| t.tags.contains(&PERSONAL_TAG.to_string())).collect::<Vec<&Transaction>>() {
-                            personal_total += transaction.amount;
-                        }
+                        let personal_total = shown.iter()
+                            .filter(|t| t.tags.contains(&PERSONAL_TAG.to_string()))
+                            .filter_map(|t| home_amount(t))
+                            .sum::<f64>();
                         ui.label("Personal: ");
-                        ui.label(RichText::new(format!("${:.2}", personal_total))
+                        ui.label(RichText::new(format!("{} {:.2}", home, personal_total))
                             .strong()
                             .size(20.0));
                         ui.separator();
 
-                        let mut joint_total = 0f64;
-                        for transaction in &*t.iter()
-                            .filter(|t| t.tags.contains(&JOINT_TAG.to_string())).collect::<Vec<&Transaction>>() {
-                            joint_total += transaction.amount;
-                        }
+                        let joint_total = shown.iter()
+                            .filter(|t| t.tags.contains(&JOINT_TAG.to_string()))
+                            .filter_map(|t| home_amount(t))
+                            .sum::<f64>();
                         ui.label("Joint: ");
-                        ui.label(RichText::new(format!("${:.2}", joint_total))
+                        ui.label(RichText::new(format!("{} {:.2}", home, joint_total))
                             .strong()
                             .size(20.0));
                         ui.separator();
 
+                        let unconverted = shown.iter().filter(|t| home_amount(t).is_none()).count();
+                        if unconverted > 0 {
+                            ui.colored_label(ui.visuals().error_fg_color, format!("{} without exchange rate", unconverted))
+                                .on_hover_text("Import exchange rates in Settings to include them in the totals");
+                            ui.separator();
+                        }
+
+                        if let Some(key) = &self.current_statement {
+                            for budget in self.budgets.iter().filter(|b| b.is_active()) {
+                                let spent = budget.spent(&self.history, key, &t, &self.currency_settings);
+                                let mut bar = egui::ProgressBar::new((spent / budget.limit).clamp(0.0, 1.0) as f32)
+                                    .desired_width(120.0)
+                                    .text(format!("{:.2} / {:.2}", spent, budget.limit));
+                                if budget.is_over(spent) {
+                                    bar = bar.fill(ui.visuals().error_fg_color);
+                                }
+                                ui.label(format!("{}: ", budget.tag));
+                                ui.add(bar)
+                                    .on_hover_text(format!("{} budget", budget.period.name()));
+                                ui.separator();
+                            }
+                        }
+
                     });
-                    self.build_table(ui);
+                    self.build_table(ui, &rows);
             });
         });
     }
@@ -0,0 +1,172 @@
+#[cfg(feature = "gui")]
+use anyhow::anyhow;
+#[cfg(any(feature = "gui", not(target_arch = "wasm32")))]
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+
+/// Receipt attached to a transaction, the file content is kept in the local attachment store
+/// (IndexedDB on web, the app data directory on native) and looked up by `id`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct Attachment {
+    pub id: String,
+    pub file_name: String,
+}
+
+impl Attachment {
+    /// The id is the SHA-256 of the content so attaching the same receipt twice stores it once,
+    /// and stays the same across builds.
+    pub fn new(file_name: String, data: &[u8]) -> Self {
+        let hash = Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        let extension = file_name.rsplit_once('.')
+            .map(|(_, e)| e.to_lowercase())
+            .unwrap_or_default();
+        Self {
+            id: format!("{}.{}", hash, extension),
+            file_name,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+pub const RECEIPT_EXTENSIONS: &[&str] = &["pdf", "png", "jpg", "jpeg"];
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "gui"))]
+mod store {
+    use std::path::PathBuf;
+    use anyhow::{anyhow, Result};
+
+    //must match the app name passed to eframe::run_native so attachments sit next to app.ron
+    const APP_ID: &str = "eframe template";
+
+    fn directory() -> Result<PathBuf> {
+        let directory = eframe::storage_dir(APP_ID)
+            .ok_or_else(|| anyhow!("No app data directory on this platform"))?
+            .join("attachments");
+        std::fs::create_dir_all(&directory)?;
+        Ok(directory)
+    }
+
+    pub async fn save(id: &str, data: &[u8]) -> Result<()> {
+        std::fs::write(directory()?.join(id), data)?;
+        Ok(())
+    }
+
+    pub async fn load(id: &str) -> Result<Vec<u8>> {
+        Ok(std::fs::read(directory()?.join(id))?)
+    }
+
+    pub async fn remove(id: &str) -> Result<()> {
+        let path = directory()?.join(id);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+//the app data directory comes from eframe, headless builds have nowhere to keep attachments
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "gui")))]
+mod store {
+    use anyhow::{anyhow, Result};
+
+    pub async fn save(_id: &str, _data: &[u8]) -> Result<()> {
+        Err(anyhow!("Attachments need the gui feature"))
+    }
+}
+
+//the headless web build only has the JS API, which does not keep attachments
+#[cfg(all(target_arch = "wasm32", feature = "gui"))]
+mod store {
+    use anyhow::{anyhow, Result};
+    use js_sys::{Promise, Uint8Array};
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+    use web_sys::{IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode};
+
+    const DATABASE: &str = "credit_card_billsplit";
+    const STORE: &str = "attachments";
+
+    fn js_error(e: JsValue) -> anyhow::Error {
+        anyhow!("{:?}", e)
+    }
+
+    //resolves with the request result once IndexedDB fires success or error
+    async fn wait(request: &IdbRequest) -> Result<JsValue> {
+        let promise = Promise::new(&mut |resolve, reject| {
+            let success = request.clone();
+            let onsuccess = Closure::once_into_js(move |_: web_sys::Event| {
+                let _ = resolve.call1(&JsValue::UNDEFINED, &success.result().unwrap_or(JsValue::UNDEFINED));
+            });
+            let onerror = Closure::once_into_js(move |_: web_sys::Event| {
+                let _ = reject.call0(&JsValue::UNDEFINED);
+            });
+            request.set_onsuccess(Some(onsuccess.unchecked_ref()));
+            request.set_onerror(Some(onerror.unchecked_ref()));
+        });
+        JsFuture::from(promise).await.map_err(js_error)
+    }
+
+    async fn object_store(mode: IdbTransactionMode) -> Result<IdbObjectStore> {
+        let factory = web_sys::window()
+            .and_then(|w| w.indexed_db().ok().flatten())
+            .ok_or_else(|| anyhow!("IndexedDB is not available"))?;
+        let request = factory.open_with_u32(DATABASE, 1).map_err(js_error)?;
+
+        let upgrade = request.clone();
+        let onupgradeneeded = Closure::once_into_js(move |_: web_sys::Event| {
+            if let Ok(db) = upgrade.result() {
+                let _ = db.unchecked_into::<IdbDatabase>().create_object_store(STORE);
+            }
+        });
+        request.set_onupgradeneeded(Some(onupgradeneeded.unchecked_ref()));
+
+        let db: IdbDatabase = wait(&request).await?.unchecked_into();
+        db.transaction_with_str_and_mode(STORE, mode)
+            .and_then(|t| t.object_store(STORE))
+            .map_err(js_error)
+    }
+
+    pub async fn save(id: &str, data: &[u8]) -> Result<()> {
+        let store = object_store(IdbTransactionMode::Readwrite).await?;
+        let request = store.put_with_key(&Uint8Array::from(data), &JsValue::from_str(id))
+            .map_err(js_error)?;
+        wait(&request).await?;
+        Ok(())
+    }
+
+    #[cfg(feature = "gui")]
+    pub async fn load(id: &str) -> Result<Vec<u8>> {
+        let store = object_store(IdbTransactionMode::Readonly).await?;
+        let request = store.get(&JsValue::from_str(id)).map_err(js_error)?;
+        let value = wait(&request).await?;
+        if value.is_undefined() {
+            return Err(anyhow!("Attachment {} not found", id));
+        }
+        Ok(Uint8Array::new(&value).to_vec())
+    }
+
+    #[cfg(feature = "gui")]
+    pub async fn remove(id: &str) -> Result<()> {
+        let store = object_store(IdbTransactionMode::Readwrite).await?;
+        let request = store.delete(&JsValue::from_str(id)).map_err(js_error)?;
+        wait(&request).await?;
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "gui", not(target_arch = "wasm32")))]
+pub async fn save(attachment: &Attachment, data: &[u8]) -> Result<()> {
+    store::save(&attachment.id, data).await
+}
+
+#[cfg(feature = "gui")]
+pub async fn load(attachment: &Attachment) -> Result<Vec<u8>> {
+    store::load(&attachment.id).await
+        .map_err(|e| anyhow!("Unable to load {}: {}", attachment.file_name, e))
+}
+
+#[cfg(feature = "gui")]
+pub async fn remove(attachment: &Attachment) -> Result<()> {
+    store::remove(&attachment.id).await
+}
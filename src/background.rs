@@ -0,0 +1,114 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::Arc;
+use rfd::AsyncFileDialog;
+use crate::bill_reader::{ReaderOptions, Statement};
+use crate::import::{self, ImportResult, Progress};
+
+enum Message {
+    Progress(Progress),
+    Finished(Vec<Statement>, Vec<ImportResult>),
+    Cancelled,
+}
+
+/// How a parse job ended, `poll` returns it once.
+pub enum Outcome {
+    Finished(Vec<Statement>, Vec<ImportResult>),
+    Cancelled,
+}
+
+//the future is built on the thread running it, pdfium documents cannot move between threads
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn<F: Future<Output = ()> + 'static>(job: impl FnOnce() -> F + Send + 'static) {
+    std::thread::spawn(move || async_std::task::block_on(job()));
+}
+
+//not a Web Worker: pdfium is initialised on the page's main thread, so the job shares it with the
+//UI and only yields to the browser between pages, a long page still holds the tab
+#[cfg(target_arch = "wasm32")]
+fn spawn<F: Future<Output = ()> + 'static>(job: impl FnOnce() -> F + 'static) {
+    wasm_bindgen_futures::spawn_local(job());
+}
+
+/// File pick and statement parsing running off the UI thread on native, and on the main thread
+/// between frames on the web. Progress and the result come back over a channel.
+pub struct ParseJob {
+    receiver: Receiver<Message>,
+    cancel: Arc<AtomicBool>,
+    pub progress: Option<Progress>,
+}
+
+impl ParseJob {
+    /// Asks for the statement files, then reads them.
+    pub fn pick(ctx: &egui::Context, options: ReaderOptions) -> Self {
+        Self::start(ctx, options, || async {
+            let Some(picked) = AsyncFileDialog::new()
+                .add_filter("Statements", &["pdf", "csv"])
+                .set_directory("/")
+                .pick_files()
+                .await else {
+                return Vec::new();
+            };
+            let mut files = Vec::new();
+            for file in picked {
+                files.push((file.file_name(), Ok(file.read().await)));
+            }
+            files
+        })
+    }
+
+    /// Reads files which are already loaded, such as the ones dropped on the window.
+    pub fn read(ctx: &egui::Context, options: ReaderOptions, files: Vec<(String, Result<Vec<u8>, String>)>) -> Self {
+        Self::start(ctx, options, move || async move { files })
+    }
+
+    fn start<F>(ctx: &egui::Context, options: ReaderOptions, files: impl FnOnce() -> F + Send + 'static) -> Self
+    where
+        F: Future<Output = Vec<(String, Result<Vec<u8>, String>)>> + 'static,
+    {
+        let (sender, receiver) = channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancelled = Arc::clone(&cancel);
+        let ctx = ctx.clone();
+        spawn(move || async move {
+            let send = |message: Message| {
+                let _ = sender.send(message);
+                ctx.request_repaint();
+            };
+            let files = files().await;
+            let read = import::read_files(files, &options, true, |progress| {
+                send(Message::Progress(progress.clone()));
+                !cancelled.load(Ordering::Relaxed)
+            }).await;
+            send(match read {
+                Some((statements, results)) => Message::Finished(statements, results),
+                None => Message::Cancelled,
+            });
+        });
+        Self { receiver, cancel, progress: None }
+    }
+
+    /// Stops after the page being read, `poll` then returns `Outcome::Cancelled`.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelling(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// Takes the messages sent since the last frame, the outcome once the job is over.
+    pub fn poll(&mut self) -> Option<Outcome> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(Message::Progress(progress)) => self.progress = Some(progress),
+                Ok(Message::Finished(statements, results)) => return Some(Outcome::Finished(statements, results)),
+                Ok(Message::Cancelled) => return Some(Outcome::Cancelled),
+                Err(TryRecvError::Empty) => return None,
+                //the job panicked, there is nothing left to wait for
+                Err(TryRecvError::Disconnected) => return Some(Outcome::Cancelled),
+            }
+        }
+    }
+}
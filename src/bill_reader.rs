@@ -1,25 +1,136 @@
+//! Statement parsing: [`CreditCardBillReader`] turns a PDF credit card statement into a
+//! [`Statement`] of [`Transaction`]s. It runs a [`TextExtractor`] for the lines of each page and
+//! then a [`StatementParser`] for the transactions on them, so parsing also works on plain text
+//! with [`PlainTextExtractor`] and no pdfium.
+
 use std::fmt::{Display, Formatter};
-use std::ops::Index;
-use std::time::SystemTime;
+use std::future::Future;
+use anyhow::Result;
 use log::{log, Level};
-use pdfium_render::prelude::{PdfPageText, Pdfium, PdfiumError};
-use regex::{Regex, RegexBuilder};
+use pdfium_render::prelude::{PdfPageText, Pdfium};
+use crate::profile::Profile;
+
+pub use crate::attachment::Attachment;
+pub use crate::currency::ForeignAmount;
+pub use crate::merchant::Merchant;
+pub use crate::amount::AmountFormat;
+pub use crate::profile::StatementProfile;
 
+/// Tag of transactions paid by the owner of the personal card alone.
 pub const PERSONAL_TAG: &str = r"Personal";
+/// Tag of transactions split evenly between everyone.
 pub const JOINT_TAG: &str = r"Joint";
 
-#[derive(serde::Deserialize, serde::Serialize)]
+pub(crate) const MONTHS: [&str; 12] = ["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+
+/// Statement line a transaction was read from. `bounds` is left, bottom, right and top in PDF
+/// points from the bottom left corner of page `page` (0 based).
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct Source {
+    pub statement: Option<Attachment>,
+    pub page: usize,
+    pub bounds: Option<[f32; 4]>,
+}
+
+/// Card number of a card section header, which may be grouped 4-4-4-4, 4-6-5 like Amex, or
+/// have all but the last digits masked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CardNumber {
+    /// Digits with the masked ones as "X", "XXXXXXXXXXXX1234" or "378282246310005".
+    pub id: String,
+    /// Grouped like the statement prints it, "XXXX XXXX XXXX 1234" or "3782 822463 10005".
+    pub display: String,
+}
+
+impl CardNumber {
+    /// Card number printed as `text`, with "X", "*" or "•" for masked digits and spaces or
+    /// dashes between the groups. `None` when it is not 12 to 19 characters with the last four
+    /// digits shown.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut groups = Vec::new();
+        for group in text.trim().split([' ', '-']).filter(|g| !g.is_empty()) {
+            groups.push(group.chars()
+                .map(|c| match c {
+                    '0'..='9' => Some(c),
+                    'X' | 'x' | '*' | '\u{2022}' => Some('X'),
+                    _ => None,
+                })
+                .collect::<Option<String>>()?);
+        }
+        let id = groups.concat();
+        let last = id.get(id.len().saturating_sub(4)..)?;
+        if !(12..=19).contains(&id.len()) || !last.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        Some(Self { display: groups.join(" "), id })
+    }
+}
+
+/// One statement line. Fields added after the first release are `#[serde(default)]`, so JSON
+/// written by older versions still loads.
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
 pub struct Transaction {
+    /// "05 JUN", statements do not print the year.
     pub date: String,
+    /// Statement text, without the foreign amount when there is one.
     pub description: String,
+    /// Charged amount, negative for refunds and payments.
     pub amount: f64,
+    /// Card number as printed, "1234 5678 9012 5136" or "XXXX XXXX XXXX 5136".
     pub card: String,
-    pub tags: Vec<String>
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub merchant: Merchant,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    /// Currency of `amount`. Empty when read outside the app, or stored before the app recorded
+    /// it, and then taken as the home currency.
+    #[serde(default)]
+    pub currency: String,
+    /// Amount in the currency the merchant charged, for foreign transactions.
+    #[serde(default)]
+    pub original: Option<ForeignAmount>,
+    #[serde(default)]
+    pub reviewed: bool,
+    #[serde(default)]
+    pub source: Option<Source>,
+    /// [`CardNumber::id`] of `card`, empty for transactions which were not read from a statement.
+    #[serde(default)]
+    pub card_id: String,
 }
 
 impl Transaction {
     pub fn new(date: String, description: String, amount: f64, card: String, tags: Vec<String>) -> Self {
-        Self { date, description, amount, card, tags}
+        Self { date, description, amount, card, tags, merchant: Merchant::default(), notes: String::new(), attachments: Vec::new(), currency: String::new(), original: None, reviewed: false, source: None, card_id: String::new() }
+    }
+
+    /// Date, description, amount and card, which identify the transaction when the table is
+    /// sorted or the statement is opened again.
+    pub fn key(&self) -> (String, String, String, String) {
+        (self.date.clone(), self.description.clone(), format!("{:.2}", self.amount), self.card.clone())
+    }
+
+    /// [`CardNumber::id`] of the card, or the card without spaces for transactions which were
+    /// not read from a statement. Totals per card and statement keys group by it, so a card
+    /// printed "1234-5678-..." on one statement and "1234 5678 ..." on another is one card.
+    pub fn card_key(&self) -> String {
+        if self.card_id.is_empty() { self.card.replace(' ', "") } else { self.card_id.clone() }
+    }
+
+    /// Whether the card number ends with `ending`, ignoring spaces. "X" stands for a masked digit.
+    pub fn card_ends_with(&self, ending: &str) -> bool {
+        self.card_key().to_uppercase().ends_with(&ending.replace(' ', "").to_uppercase())
+    }
+
+    /// Day and month (1-12) of the "05 JUN" statement date, statements do not print the year.
+    pub fn day_month(&self) -> Option<(u32, u32)> {
+        let mut parts = self.date.split_whitespace();
+        let day = parts.next()?.parse::<u32>().ok()?;
+        let month = parts.next()?.to_uppercase();
+        let month = MONTHS.iter().position(|m| *m == month)? as u32 + 1;
+        Some((day, month))
     }
 }
 
@@ -34,112 +145,458 @@ impl Display for Transaction {
     }
 }
 
+/// Transactions read from one statement file, with what the reader could not make sense of.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default)]
+pub struct Statement {
+    pub transactions: Vec<Transaction>,
+    pub pages: usize,
+    /// Card numbers of the card sections found, in statement order.
+    #[serde(default)]
+    pub cards: Vec<String>,
+    /// Lines inside a card section which are not a transaction, such as headers and totals.
+    #[serde(default)]
+    pub unmatched_lines: Vec<String>,
+    /// Name of the profile which read the statement, empty when none matched.
+    #[serde(default)]
+    pub profile: String,
+    /// Year printed on the statement, when the profile knows where to find it.
+    #[serde(default)]
+    pub year: Option<i32>,
+}
+
+/// Which of the reader's patterns a statement line matches.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum LineKind {
+    Card,
+    Transaction,
+    Ignored,
+    Other,
+}
+
+/// Reads statements of one kind of document, usually by running a [`TextExtractor`] and then a
+/// [`StatementParser`].
 pub trait BillReader {
-    fn read(&self, data: Vec<u8>) -> Vec<Transaction>;
+    /// Reads the statement in `data`, failing when its text cannot be extracted, such as for a
+    /// corrupt or encrypted PDF. `progress` gets the page number and page count after each
+    /// page, returning false stops the read with `None`. The future gives the UI a turn between
+    /// pages and is not `Send`, pdfium documents stay on the thread which opened them.
+    fn read(&self, data: Vec<u8>, progress: impl FnMut(usize, usize) -> bool) -> impl Future<Output = Option<Result<Statement>>>;
+
+    /// Reads the whole statement on the current thread.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_blocking(&self, data: Vec<u8>) -> Result<Statement> {
+        async_std::task::block_on(self.read(data, |_, _| true)).unwrap_or_else(|| Ok(Statement::default()))
+    }
 }
 
-pub struct CreditCardBillReader {
-    pdf_reader: Pdfium,
-    card_regex: Regex,
-    transaction_regex: Regex,
+/// How statement lines are turned into transactions.
+#[derive(Clone, Debug)]
+pub struct ReaderOptions {
+    /// Cards whose number ends with one of these are tagged Personal, every other card Joint.
+    pub personal_cards: Vec<String>,
+    /// Statement layouts, the first whose fingerprints are on the first page reads the statement.
+    pub profiles: Vec<StatementProfile>,
 }
 
-impl CreditCardBillReader {
-    pub fn default() -> Self {
+impl Default for ReaderOptions {
+    fn default() -> Self {
         Self {
-            pdf_reader: Pdfium::default(),
-            //**** ************ CARD **** **** **** **** - *** *** ****
-            card_regex: RegexBuilder::new(r".* CARD (\d{4} \d{4} \d{4} \d{4}) - .*")
-                .case_insensitive(true)
-                .build().unwrap(),
-            //05 JUN ********** Singapore SG (3.85)
-            transaction_regex: RegexBuilder::new(r"(\d{2} [a-z]{3}) (.*) (\(?[0-9,]*\.\d{2}\)?)")
-                .case_insensitive(true)
-                .build().unwrap()
+            personal_cards: vec!["5136".to_string()],
+            profiles: vec![StatementProfile::citibank()],
         }
     }
 }
 
-impl BillReader for CreditCardBillReader {
-    fn read(&self, data: Vec<u8>) -> Vec<Transaction> {
-        let mut transactions = Vec::<Transaction>::new();
-        match self.pdf_reader.load_pdf_from_byte_vec(data, None) {
-            Ok(d) => {
-                //log!(Level::Info, "Pages: {}", d.pages().len());
-                let mut card = String::default();
-                d.pages().iter()
-                    .enumerate()
-                    .for_each(|(index, page)|{
-                        match page.text() {
-                            Ok(t) => {
-                                for l in t.all().lines() {
-                                    //log!(Level::Info, "{}", l);
-                                    let card_captures = self.card_regex.captures(l)
-                                        .and_then(|c| {
-                                            //log!(Level::Info, "{:?}", c);
-                                            if c.len() == 2 {
-                                                card = c.index(1).parse().unwrap();
-                                                //log!(Level::Info, "{}", card);
-                                            }
-                                            Some(true)
-                                        });
-                                    if card_captures.is_some() {
-                                        continue;
-                                    }
-
-                                    if card == "" {
-                                        continue;
-                                    }
-
-                                    let transaction_captures = self.transaction_regex.captures(l)
-                                        .and_then(|c|{
-                                            //log!(Level::Info, "{:?}", c);
-                                            if c.len() == 4 {
-                                                let mut amount_str : String = c.index(3).parse().unwrap();
-                                                let mut amount = 0.0;
-                                                if amount_str.starts_with("(") &&
-                                                    amount_str.ends_with(")") {
-                                                    amount_str = amount_str.replace("(", "");
-                                                    amount_str = amount_str.replace(")", "");
-                                                    amount = -(amount_str.replace(",", "").parse::<f64>().unwrap());
-                                                }else{
-                                                    amount = amount_str.replace(",", "").parse::<f64>().unwrap();
-                                                }
-
-                                                let tags = vec![if card.ends_with("5136") { PERSONAL_TAG.to_string() } else { JOINT_TAG.to_string()}];
-
-                                                let transaction = Transaction::new(
-                                                    c.index(1).parse().unwrap(),
-                                                    c.index(2).parse().unwrap(),
-                                                    amount,
-                                                    card.clone(),
-                                                    tags);
-                                                //log!(Level::Info, "{:?}", transaction);
-                                                return Some(transaction)
-                                            }
-                                            None
-                                        });
-                                    if transaction_captures.is_some() {
-                                        transactions.push(transaction_captures.unwrap());
-                                        continue;
-                                    }
-
-                                    log!(Level::Info, "{}", l);
-                                }
+/// One line of statement text, `bounds` is the box around it as in [`Source`] when known.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextLine {
+    pub text: String,
+    pub bounds: Option<[f32; 4]>,
+}
+
+impl TextLine {
+    fn plain(text: &str) -> Self {
+        Self { text: text.to_string(), bounds: None }
+    }
+}
+
+/// Separates the pages of plain text statements, the way a printer's form feed does.
+pub const PAGE_BREAK: char = '\u{c}';
+
+/// First stage of reading a statement: the lines of text on each page of a document.
+pub trait TextExtractor {
+    /// Lines of each page of `data`. `progress` gets the page number and page count after each
+    /// page, returning false stops with `None`.
+    fn extract(&self, data: Vec<u8>, progress: impl FnMut(usize, usize) -> bool) -> impl Future<Output = Option<Result<Vec<Vec<TextLine>>>>>;
+}
+
+/// Text of PDF documents through pdfium, with the box around each line.
+pub struct PdfiumExtractor {
+    pdfium: Pdfium,
+}
+
+impl PdfiumExtractor {
+    pub fn new(pdfium: Pdfium) -> Self {
+        Self { pdfium }
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn pdfium(&self) -> &Pdfium {
+        &self.pdfium
+    }
+}
+
+impl TextExtractor for PdfiumExtractor {
+    async fn extract(&self, data: Vec<u8>, mut progress: impl FnMut(usize, usize) -> bool) -> Option<Result<Vec<Vec<TextLine>>>> {
+        //the wasm error holds a JS value, which anyhow cannot carry across threads
+        let document = match self.pdfium.load_pdf_from_byte_vec(data, None) {
+            Ok(document) => document,
+            Err(e) => return Some(Err(anyhow::anyhow!("{}", e))),
+        };
+        let pages = document.pages();
+        let count = pages.len() as usize;
+        let mut text = Vec::new();
+        for (index, page) in pages.iter().enumerate() {
+            text.push(page.text().map(|t| page_lines(&t)).unwrap_or_default());
+            if !progress(index + 1, count) {
+                return None;
+            }
+            //gives the UI a turn between pages
+            yield_now().await;
+        }
+        Some(Ok(text))
+    }
+}
+
+/// Text of PDF documents in pure Rust, for machines without the pdfium library. Lines have no
+/// bounds, so the statement viewer shows the page without highlighting the line.
+#[cfg(feature = "pure-rust-pdf")]
+pub struct PdfExtractExtractor;
+
+#[cfg(feature = "pure-rust-pdf")]
+impl TextExtractor for PdfExtractExtractor {
+    async fn extract(&self, data: Vec<u8>, mut progress: impl FnMut(usize, usize) -> bool) -> Option<Result<Vec<Vec<TextLine>>>> {
+        //pdf-extract panics on some fonts it does not know, that is one unreadable statement
+        let pages = match std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem_by_pages(&data)) {
+            Ok(Ok(pages)) => pages,
+            Ok(Err(e)) => return Some(Err(anyhow::anyhow!("{}", e))),
+            Err(_) => return Some(Err(anyhow::anyhow!("The PDF text could not be extracted"))),
+        };
+        let count = pages.len();
+        let mut text = Vec::new();
+        for (index, page) in pages.iter().enumerate() {
+            text.push(page.lines().map(TextLine::plain).collect());
+            if !progress(index + 1, count) {
+                return None;
+            }
+        }
+        Some(Ok(text))
+    }
+}
+
+/// PDF text extractor of a [`CreditCardBillReader`].
+pub enum PdfExtractor {
+    Pdfium(PdfiumExtractor),
+    #[cfg(feature = "pure-rust-pdf")]
+    PureRust(PdfExtractExtractor),
+}
+
+impl PdfExtractor {
+    /// The pdfium library, which the reader can also be without.
+    #[cfg(feature = "gui")]
+    pub(crate) fn pdfium(&self) -> Option<&Pdfium> {
+        match self {
+            PdfExtractor::Pdfium(extractor) => Some(extractor.pdfium()),
+            #[cfg(feature = "pure-rust-pdf")]
+            PdfExtractor::PureRust(_) => None,
+        }
+    }
+}
+
+impl TextExtractor for PdfExtractor {
+    async fn extract(&self, data: Vec<u8>, progress: impl FnMut(usize, usize) -> bool) -> Option<Result<Vec<Vec<TextLine>>>> {
+        match self {
+            PdfExtractor::Pdfium(extractor) => extractor.extract(data, progress).await,
+            #[cfg(feature = "pure-rust-pdf")]
+            PdfExtractor::PureRust(extractor) => extractor.extract(data, progress).await,
+        }
+    }
+}
+
+/// Text statements such as the anonymised samples, UTF-8 with a form feed between pages.
+pub struct PlainTextExtractor;
+
+impl PlainTextExtractor {
+    /// Lines of each page of `text`.
+    pub fn pages(text: &str) -> Vec<Vec<TextLine>> {
+        text.split(PAGE_BREAK)
+            .map(|page| page.trim_matches(['\r', '\n']).lines().map(TextLine::plain).collect())
+            .collect()
+    }
+}
+
+impl TextExtractor for PlainTextExtractor {
+    async fn extract(&self, data: Vec<u8>, mut progress: impl FnMut(usize, usize) -> bool) -> Option<Result<Vec<Vec<TextLine>>>> {
+        let pages = match String::from_utf8(data) {
+            Ok(text) => Self::pages(&text),
+            Err(e) => return Some(Err(e.into())),
+        };
+        progress(pages.len(), pages.len()).then_some(Ok(pages))
+    }
+}
+
+/// Second stage of reading a statement: the transactions on the lines of text, read with the
+/// first [`StatementProfile`] whose fingerprints are on the first page.
+pub struct StatementParser {
+    options: ReaderOptions,
+    profiles: Vec<Profile>,
+}
+
+impl StatementParser {
+    /// Profiles with an invalid pattern are left out, [`StatementProfile::from_json`] reports
+    /// them when loading.
+    pub fn new(options: ReaderOptions) -> Self {
+        let profiles = options.profiles.iter()
+            .filter_map(|p| p.compile().map_err(|e| log!(Level::Warn, "{}", e)).ok())
+            .collect();
+        Self { options, profiles }
+    }
+
+    /// Profile reading the statement whose first page has `lines`.
+    pub(crate) fn profile(&self, lines: &[String]) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.detects(lines))
+    }
+
+    /// Transactions on `pages`, without any when no profile matches the first page.
+    pub fn parse(&self, pages: &[Vec<TextLine>]) -> Statement {
+        let first_page = pages.first()
+            .map(|lines| lines.iter().map(|l| l.text.clone()).collect::<Vec<String>>())
+            .unwrap_or_default();
+        match self.profile(&first_page) {
+            Some(profile) => self.parse_with(profile, pages),
+            None => {
+                log!(Level::Warn, "No statement profile matches this statement");
+                Statement { pages: pages.len(), ..Default::default() }
+            }
+        }
+    }
+
+    /// Transactions on `pages` read with `profile`, whether or not its fingerprints are there.
+    pub(crate) fn parse_with(&self, profile: &Profile, pages: &[Vec<TextLine>]) -> Statement {
+        let first_page = pages.first()
+            .map(|lines| lines.iter().map(|l| l.text.clone()).collect::<Vec<String>>())
+            .unwrap_or_default();
+        let mut statement = Statement {
+            pages: pages.len(),
+            profile: profile.name.clone(),
+            year: profile.year(&first_page),
+            ..Default::default()
+        };
+        //the card number carries over to the next page
+        let mut card = None;
+        for (index, lines) in pages.iter().enumerate() {
+            self.read_page(profile, index, lines, &mut card, &mut statement);
+        }
+        statement
+    }
+
+    /// Adds the transactions on the lines of page `page` to the statement.
+    fn read_page(&self, profile: &Profile, page: usize, lines: &[TextLine], card: &mut Option<CardNumber>, statement: &mut Statement) {
+        for TextLine { text: l, bounds } in lines {
+            let l = l.as_str();
+            match profile.line_kind(l) {
+                LineKind::Ignored => continue,
+                LineKind::Card => {
+                    match profile.card(l) {
+                        Some(number) => {
+                            if !statement.cards.contains(&number.display) {
+                                statement.cards.push(number.display.clone());
                             }
-                            Err(_) => {}
+                            *card = Some(number);
                         }
-                        //log!(Level::Info, "{}", page.text().unwrap().all());
+                        None => log!(Level::Warn, "No card number in \"{}\"", l),
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            let Some(card) = card.as_ref() else {
+                continue;
+            };
+
+            if let Some((date, description, amount)) = profile.transaction(l) {
+                let mut transaction = Transaction::new(date, description, amount, card.display.clone(), Vec::new());
+                transaction.card_id = card.id.clone();
+                let personal = self.options.personal_cards.iter().any(|c| !c.is_empty() && transaction.card_ends_with(c));
+                transaction.tags.push(if personal { PERSONAL_TAG.to_string() } else { JOINT_TAG.to_string()});
+
+                if let Some((description, currency, foreign_amount)) = profile.foreign_amount(&transaction.description) {
+                    transaction.original = Some(ForeignAmount {
+                        currency,
+                        amount: foreign_amount.copysign(amount),
                     });
+                    transaction.description = description;
+                }
+                transaction.source = Some(Source { statement: None, page, bounds: *bounds });
+                statement.transactions.push(transaction);
+                continue;
             }
+
+            match profile.amount_error(l) {
+                Some(e) => log!(Level::Warn, "{}", e),
+                None => log!(Level::Info, "{}", l),
+            }
+            if !l.trim().is_empty() {
+                statement.unmatched_lines.push(l.to_string());
+            }
+        }
+    }
+}
+
+/// The pdfium library next to the executable, or the one installed on the system. On the web
+/// it is the one the page initialised.
+pub fn bind_pdfium() -> Result<Pdfium> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let bindings = Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path("./"))
+        .or_else(|_| Pdfium::bind_to_system_library())?;
+    //the wasm error holds a JS value, which anyhow cannot carry across threads
+    #[cfg(target_arch = "wasm32")]
+    let bindings = Pdfium::bind_to_system_library().map_err(|e| anyhow::anyhow!("{}", e))?;
+    Ok(Pdfium::new(bindings))
+}
+
+/// Reader of PDF credit card statements laid out as one of the [`StatementProfile`]s,
+/// Citibank's by default: a [`PdfExtractor`] followed by a [`StatementParser`].
+pub struct CreditCardBillReader {
+    extractor: PdfExtractor,
+    parser: StatementParser,
+}
+
+#[cfg(feature = "pure-rust-pdf")]
+impl Default for CreditCardBillReader {
+    /// Reader with the default options using pdfium, or the pure Rust extractor when pdfium
+    /// cannot be loaded. Without the `pure-rust-pdf` feature there is no default reader, use
+    /// [`CreditCardBillReader::new`] to handle a missing pdfium.
+    fn default() -> Self {
+        let options = ReaderOptions::default();
+        match bind_pdfium() {
+            Ok(pdfium) => Self::with_pdfium(pdfium, options),
+            Err(_) => Self::with_extractor(PdfExtractor::PureRust(PdfExtractExtractor), options),
+        }
+    }
+}
+
+impl CreditCardBillReader {
+    /// Reader using pdfium, see [`bind_pdfium`]. Without pdfium it falls back to the pure Rust
+    /// extractor of the `pure-rust-pdf` feature, or fails when the feature is off.
+    pub fn new(options: ReaderOptions) -> Result<Self> {
+        match bind_pdfium() {
+            Ok(pdfium) => Ok(Self::with_pdfium(pdfium, options)),
+            #[cfg(feature = "pure-rust-pdf")]
             Err(e) => {
-                log!(Level::Error, "{}", e.to_string());
+                log!(Level::Info, "Reading PDFs without pdfium: {}", e);
+                Ok(Self::with_extractor(PdfExtractor::PureRust(PdfExtractExtractor), options))
             }
-        };
+            #[cfg(not(feature = "pure-rust-pdf"))]
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reader using an already bound pdfium, such as one statically linked.
+    pub fn with_pdfium(pdfium: Pdfium, options: ReaderOptions) -> Self {
+        Self::with_extractor(PdfExtractor::Pdfium(PdfiumExtractor::new(pdfium)), options)
+    }
+
+    pub fn with_extractor(extractor: PdfExtractor, options: ReaderOptions) -> Self {
+        Self {
+            extractor,
+            parser: StatementParser::new(options),
+        }
+    }
 
-        transactions
+    pub fn extractor(&self) -> &PdfExtractor {
+        &self.extractor
+    }
+
+    pub fn parser(&self) -> &StatementParser {
+        &self.parser
     }
 }
 
+/// Lines of the page text with the box around their characters, text positions count UTF-16
+/// units the same way pdfium numbers its characters.
+pub(crate) fn page_lines(text: &PdfPageText<'_>) -> Vec<TextLine> {
+    let chars = text.chars();
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for line in text.all().split('\n') {
+        let length = line.encode_utf16().count();
+        let bounds = (start..start + length)
+            .filter_map(|index| chars.get(index).ok()?.loose_bounds().ok())
+            .filter(|r| r.right.value > r.left.value && r.top.value > r.bottom.value)
+            .map(|r| [r.left.value, r.bottom.value, r.right.value, r.top.value])
+            .reduce(|a, b| [a[0].min(b[0]), a[1].min(b[1]), a[2].max(b[2]), a[3].max(b[3])]);
+        lines.push(TextLine { text: line.trim_end_matches('\r').to_string(), bounds });
+        start += length + 1;
+    }
+    lines
+}
 
+#[cfg(not(target_arch = "wasm32"))]
+async fn yield_now() {
+    async_std::task::yield_now().await;
+}
+
+//lets the browser handle events and paint a frame before the next page
+#[cfg(target_arch = "wasm32")]
+async fn yield_now() {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        let scheduled = web_sys::window()
+            .map(|w| w.set_timeout_with_callback(&resolve).is_ok())
+            .unwrap_or(false);
+        if !scheduled {
+            let _ = resolve.call0(&wasm_bindgen::JsValue::NULL);
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+impl BillReader for CreditCardBillReader {
+    async fn read(&self, data: Vec<u8>, progress: impl FnMut(usize, usize) -> bool) -> Option<Result<Statement>> {
+        Some(self.extractor.extract(data, progress).await?.map(|pages| self.parser.parse(&pages)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    fn card(text: &str) -> Option<(String, String)> {
+        CardNumber::parse(text).map(|c| (c.id, c.display))
+    }
+
+    #[test]
+    fn card_numbers() {
+        assert_eq!(card("1234 5678 9012 5136"), Some(("1234567890125136".to_string(), "1234 5678 9012 5136".to_string())));
+        assert_eq!(card("1234-5678-9012-5136"), Some(("1234567890125136".to_string(), "1234 5678 9012 5136".to_string())));
+        assert_eq!(card("3782 822463 10005"), Some(("378282246310005".to_string(), "3782 822463 10005".to_string())));
+    }
+
+    #[test]
+    fn masked_card_numbers() {
+        let masked = Some(("XXXXXXXXXXXX5136".to_string(), "XXXX XXXX XXXX 5136".to_string()));
+        assert_eq!(card("xxxx xxxx xxxx 5136"), masked);
+        assert_eq!(card("**** **** **** 5136"), masked);
+        assert_eq!(card("\u{2022}\u{2022}\u{2022}\u{2022} \u{2022}\u{2022}\u{2022}\u{2022} \u{2022}\u{2022}\u{2022}\u{2022} 5136"), masked);
+    }
+
+    #[test]
+    fn not_card_numbers() {
+        assert_eq!(card("1234 5678"), None);
+        assert_eq!(card("1234 5678 9012 3456 7890"), None);
+        assert_eq!(card("1234 5678 9012 XXXX"), None);
+        assert_eq!(card("1234 5678 9012 51a6"), None);
+        assert_eq!(card(""), None);
+    }
+}
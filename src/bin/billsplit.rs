@@ -0,0 +1,15 @@
+#![warn(clippy::all, rust_2018_idioms)]
+
+//! Command line tool for scripting statement processing, `billsplit --help` lists the commands.
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    if let Err(e) = credit_card_billsplit::run_cli(std::env::args().skip(1)) {
+        eprintln!("Error: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+//the web build only runs the app
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -0,0 +1,134 @@
+use crate::bill_reader::Transaction;
+use crate::currency::CurrencySettings;
+use crate::history::{StatementHistory, StatementKey};
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum BudgetPeriod {
+    Monthly,
+    Yearly,
+}
+
+impl BudgetPeriod {
+    pub const ALL: [BudgetPeriod; 2] = [BudgetPeriod::Monthly, BudgetPeriod::Yearly];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BudgetPeriod::Monthly => "Monthly",
+            BudgetPeriod::Yearly => "Yearly",
+        }
+    }
+
+    //months are "2024-06", a yearly budget counts every statement of the year up to `month`
+    fn contains(&self, month: &str, other: &str) -> bool {
+        match self {
+            BudgetPeriod::Monthly => month == other,
+            BudgetPeriod::Yearly => month.get(..4) == other.get(..4) && other <= month,
+        }
+    }
+}
+
+/// Spending limit for the transactions with `tag`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct Budget {
+    pub tag: String,
+    pub limit: f64,
+    pub period: BudgetPeriod,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self {
+            tag: String::new(),
+            limit: 0.0,
+            period: BudgetPeriod::Monthly,
+        }
+    }
+}
+
+//in the home currency, transactions without an exchange rate are left out like in the table totals
+fn tagged_total(transactions: &[Transaction], tag: &str, currency: &CurrencySettings, month: &str) -> f64 {
+    transactions.iter()
+        .filter(|t| t.tags.iter().any(|x| x == tag))
+        .filter_map(|t| currency.home_amount(t, Some(month)))
+        .sum()
+}
+
+impl Budget {
+    /// Spent in the period ending with the month of `statement`. The stored history is used for
+    /// the other statements, `current` for `statement` itself, so edits which are not synced yet
+    /// still count.
+    pub fn spent(&self, history: &StatementHistory, statement: &StatementKey, current: &[Transaction], currency: &CurrencySettings) -> f64 {
+        self.spent_before(history, statement, currency) + tagged_total(current, &self.tag, currency, &statement.month)
+    }
+
+    /// Spent in the period ending with the month of `statement`, without `statement` itself.
+    pub fn spent_before(&self, history: &StatementHistory, statement: &StatementKey, currency: &CurrencySettings) -> f64 {
+        history.statements.iter()
+            .filter(|s| s.key() != *statement && self.period.contains(&statement.month, &s.month))
+            .map(|s| tagged_total(&s.transactions, &self.tag, currency, &s.month))
+            .sum()
+    }
+
+    /// Budgets without a tag or a limit yet are left out of the warnings and progress bars.
+    pub fn is_active(&self) -> bool {
+        !self.tag.is_empty() && self.limit > 0.0
+    }
+
+    pub fn is_over(&self, spent: f64) -> bool {
+        spent > self.limit
+    }
+}
+
+/// Budgets which were within their limit before `current` was added as `statement`, and are not anymore.
+pub fn newly_over<'a>(budgets: &'a [Budget], history: &StatementHistory, statement: &StatementKey, current: &[Transaction], currency: &CurrencySettings) -> Vec<(&'a Budget, f64)> {
+    budgets.iter()
+        .filter(|b| b.is_active())
+        .filter_map(|b| {
+            let spent = b.spent(history, statement, current, currency);
+            (b.is_over(spent) && !b.is_over(b.spent_before(history, statement, currency))).then_some((b, spent))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn charge(amount: f64, tag: &str) -> Transaction {
+        Transaction::new("05 JUN".to_string(), "GRAB".to_string(), amount, String::new(), vec![tag.to_string()])
+    }
+
+    fn june() -> StatementKey {
+        StatementKey { month: "2024-06".to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn budgets_without_a_limit_are_not_active() {
+        let unset = Budget { tag: "Joint".to_string(), ..Default::default() };
+        let untagged = Budget { limit: 100.0, ..Default::default() };
+        assert!(!unset.is_active());
+        assert!(!untagged.is_active());
+        assert!(Budget { limit: 100.0, ..unset }.is_active());
+    }
+
+    #[test]
+    fn newly_over_skips_budgets_without_a_limit() {
+        let budgets = [Budget { tag: "Joint".to_string(), ..Default::default() }];
+        let currency = CurrencySettings::default();
+        assert!(newly_over(&budgets, &StatementHistory::default(), &june(), &[charge(3.85, "Joint")], &currency).is_empty());
+    }
+
+    #[test]
+    fn newly_over_counts_other_statements_of_the_month() {
+        let budgets = [Budget { tag: "Joint".to_string(), limit: 100.0, period: BudgetPeriod::Monthly }];
+        let mut history = StatementHistory::default();
+        let other_card = StatementKey { cards: vec!["378282246310005".to_string()], ..june() };
+        history.store(&other_card, &[charge(80.0, "Joint")]);
+
+        let currency = CurrencySettings::default();
+        let over = newly_over(&budgets, &history, &june(), &[charge(30.0, "Joint")], &currency);
+        assert_eq!(over.len(), 1);
+        assert_eq!(over[0].1, 110.0);
+        assert!(newly_over(&budgets, &history, &june(), &[charge(10.0, "Joint")], &currency).is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+use std::io::Write;
+use anyhow::{anyhow, Context, Result};
+use crate::bill_reader::{CreditCardBillReader, ReaderOptions, StatementProfile, TextExtractor, Transaction};
+use crate::export;
+use crate::history;
+use crate::import;
+use crate::ledger::{self, LedgerFormat, LedgerSettings};
+use crate::merchant::MerchantNormaliser;
+use crate::redact;
+use crate::report;
+use crate::rules::{self, TagRule};
+use crate::share;
+use crate::summary::SplitSettings;
+
+const USAGE: &str = "\
+Usage: billsplit <command> <input>... [options]
+
+Inputs are PDF statements, text samples written by redact, CSV files or JSON files written
+by this tool.
+
+Commands:
+  parse        Print the transactions as JSON, or CSV with --format csv
+  apply-rules  Tag the transactions with a rules file and print them as JSON
+  summary      Print the totals per tag and who owes whom
+  export       Write CSV, XLSX, ledger, hledger, beancount or HTML
+  redact       Print the text of a PDF statement with the card numbers, merchants and amounts
+               replaced, to share a statement which is not read right
+
+Options:
+  --format <format>   json, csv, xlsx, ledger, hledger, beancount or html
+  --output <file>     Write to a file instead of the standard output
+  --rules <file>      JSON rules, [{\"condition\": \"desc:grab\", \"tag\": \"Joint\"}]
+  --profiles <file>   JSON statement profiles tried before the built-in Citibank one
+  --personal-card <ending>
+                      Card number ending tagged Personal, repeat it for more cards (default 5136)
+  --accounts <file>   JSON ledger accounts, like the app's ledger settings:
+                      {\"currency\": \"SGD\", \"card_accounts\": [{\"key\": \"5136\", \"account\": \"Liabilities:Citi\"}]}
+  --people <names>    Comma separated people sharing the bill, the first owns personal items
+  --year <year>       Year of the statement for the ledger exports
+";
+
+#[derive(Default)]
+struct Options {
+    inputs: Vec<String>,
+    format: Option<String>,
+    output: Option<String>,
+    rules: Option<String>,
+    profiles: Option<String>,
+    personal_cards: Vec<String>,
+    accounts: Option<String>,
+    people: Option<Vec<String>>,
+    year: Option<i32>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| anyhow!("{} needs a value", arg));
+            match arg.as_str() {
+                "--format" => options.format = Some(value()?.to_lowercase()),
+                "--output" => options.output = Some(value()?),
+                "--rules" => options.rules = Some(value()?),
+                "--profiles" => options.profiles = Some(value()?),
+                "--personal-card" => options.personal_cards.push(value()?.replace(' ', "")),
+                "--accounts" => options.accounts = Some(value()?),
+                "--people" => options.people = Some(value()?.split(',').map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect()),
+                "--year" => {
+                    let year = value()?;
+                    options.year = Some(year.parse().map_err(|_| anyhow!("\"{}\" is not a year", year))?);
+                }
+                _ if arg.starts_with("--") => return Err(anyhow!("Unknown option {}", arg)),
+                _ => options.inputs.push(arg),
+            }
+        }
+        if options.inputs.is_empty() {
+            return Err(anyhow!("No input files"));
+        }
+        Ok(options)
+    }
+
+    fn reader_options(&self) -> Result<ReaderOptions> {
+        let mut reader_options = ReaderOptions::default();
+        if let Some(path) = &self.profiles {
+            let text = std::fs::read_to_string(path).with_context(|| path.clone())?;
+            let mut profiles = StatementProfile::from_json(&text).map_err(|e| anyhow!("{}: {}", path, e))?;
+            profiles.append(&mut reader_options.profiles);
+            reader_options.profiles = profiles;
+        }
+        if !self.personal_cards.is_empty() {
+            reader_options.personal_cards = self.personal_cards.clone();
+        }
+        Ok(reader_options)
+    }
+
+    fn ledger_settings(&self) -> Result<LedgerSettings> {
+        let mut settings = match &self.accounts {
+            Some(path) => {
+                let text = std::fs::read_to_string(path).with_context(|| path.clone())?;
+                serde_json::from_str::<LedgerSettings>(&text).with_context(|| path.clone())?
+            }
+            None => LedgerSettings::default(),
+        };
+        if let Some(year) = self.year {
+            settings.year = year;
+        }
+        Ok(settings)
+    }
+
+    fn split_settings(&self) -> SplitSettings {
+        match &self.people {
+            Some(people) if !people.is_empty() => SplitSettings { people: people.clone() },
+            _ => SplitSettings::default(),
+        }
+    }
+}
+
+/// Transactions of every input in order, statements go through the same reader as the app.
+fn load(options: &Options) -> Result<Vec<Transaction>> {
+    let reader_options = options.reader_options()?;
+    let mut transactions = Vec::new();
+    for path in &options.inputs {
+        if path.to_lowercase().ends_with(".json") {
+            let text = std::fs::read_to_string(path).with_context(|| path.clone())?;
+            transactions.extend(serde_json::from_str::<Vec<Transaction>>(&text).with_context(|| path.clone())?);
+            continue;
+        }
+        let data = std::fs::read(path).with_context(|| path.clone())?;
+        let (read, results) = async_std::task::block_on(import::read_files(vec![(path.clone(), Ok(data))], &reader_options, false, |_| true))
+            .unwrap_or_default();
+        if let Some(error) = results.into_iter().find_map(|r| r.error) {
+            return Err(anyhow!("{}: {}", path, error));
+        }
+        let read = read.into_iter().flat_map(|s| s.transactions).collect::<Vec<Transaction>>();
+        eprintln!("{}: {} transactions", path, read.len());
+        transactions.extend(read);
+    }
+    MerchantNormaliser::new(&[]).apply(&mut transactions);
+    Ok(transactions)
+}
+
+fn write(output: Option<&str>, data: &[u8]) -> Result<()> {
+    match output {
+        Some(path) => std::fs::write(path, data).with_context(|| path.to_string()),
+        None => Ok(std::io::stdout().write_all(data)?),
+    }
+}
+
+fn to_json(transactions: &[Transaction]) -> Result<Vec<u8>> {
+    let mut json = serde_json::to_vec_pretty(transactions)?;
+    json.push(b'\n');
+    Ok(json)
+}
+
+fn parse(options: &Options) -> Result<()> {
+    let transactions = load(options)?;
+    let data = match options.format.as_deref().unwrap_or("json") {
+        "json" => to_json(&transactions)?,
+        "csv" => export::to_csv(&transactions, &options.split_settings()).into_bytes(),
+        format => return Err(anyhow!("parse writes json or csv, not {}", format)),
+    };
+    write(options.output.as_deref(), &data)
+}
+
+fn apply_rules(options: &Options) -> Result<()> {
+    let path = options.rules.as_deref().ok_or_else(|| anyhow!("apply-rules needs --rules <file>"))?;
+    let text = std::fs::read_to_string(path).with_context(|| path.to_string())?;
+    let rules = serde_json::from_str::<Vec<TagRule>>(&text).with_context(|| path.to_string())?;
+
+    let mut transactions = load(options)?;
+    let month = history::statement_month(&transactions, None);
+    let tagged = rules::apply(&rules, &mut transactions, month.as_deref()).map_err(|e| anyhow!(e))?;
+    eprintln!("Tagged {} of {} transactions", tagged, transactions.len());
+
+    let data = match options.format.as_deref().unwrap_or("json") {
+        "json" => to_json(&transactions)?,
+        "csv" => export::to_csv(&transactions, &options.split_settings()).into_bytes(),
+        format => return Err(anyhow!("apply-rules writes json or csv, not {}", format)),
+    };
+    write(options.output.as_deref(), &data)
+}
+
+fn summary(options: &Options) -> Result<()> {
+    let transactions = load(options)?;
+    let mut text = share::summary_text(&transactions, &options.split_settings());
+    text.push('\n');
+    write(options.output.as_deref(), text.as_bytes())
+}
+
+fn export(options: &Options) -> Result<()> {
+    let transactions = load(options)?;
+    let split = options.split_settings();
+    let format = options.format.as_deref().ok_or_else(|| anyhow!("export needs --format"))?;
+    let data = match format {
+        "csv" => export::to_csv(&transactions, &split).into_bytes(),
+        "xlsx" => export::to_xlsx(&transactions, &split)?,
+        "html" => report::to_html(&transactions, &split).into_bytes(),
+        format => {
+            let ledger_format = LedgerFormat::ALL.into_iter()
+                .find(|f| f.name().eq_ignore_ascii_case(format))
+                .ok_or_else(|| anyhow!("Unknown format {}", format))?;
+            ledger::to_journal(&transactions, &split, &options.ledger_settings()?, ledger_format)?.into_bytes()
+        }
+    };
+    write(options.output.as_deref(), &data)
+}
+
+fn redact(options: &Options) -> Result<()> {
+    let [path] = options.inputs.as_slice() else {
+        return Err(anyhow!("redact takes one PDF statement"));
+    };
+    let data = std::fs::read(path).with_context(|| path.clone())?;
+    let reader = CreditCardBillReader::new(options.reader_options()?)?;
+    let pages = async_std::task::block_on(reader.extractor().extract(data, |_, _| true))
+        .unwrap_or_else(|| Ok(Vec::new()))
+        .with_context(|| path.clone())?
+        .into_iter()
+        .map(|lines| lines.into_iter().map(|l| l.text).collect())
+        .collect::<Vec<Vec<String>>>();
+    let profile = reader.parser().profile(pages.first().map(Vec::as_slice).unwrap_or_default())
+        .ok_or_else(|| anyhow!("{}: no statement profile matches this statement", path))?;
+    let mut text = redact::anonymise(profile, &pages);
+    text.push('\n');
+    eprintln!("Lines which match no pattern keep their words, check them before sharing the sample");
+    write(options.output.as_deref(), text.as_bytes())
+}
+
+/// Runs the command line tool with the arguments after the program name.
+pub fn run_cli(args: impl IntoIterator<Item = String>) -> Result<()> {
+    let mut args = args.into_iter();
+    let command = args.next().unwrap_or_default();
+    match command.as_str() {
+        "parse" => parse(&Options::parse(args)?),
+        "apply-rules" => apply_rules(&Options::parse(args)?),
+        "summary" => summary(&Options::parse(args)?),
+        "export" => export(&Options::parse(args)?),
+        "redact" => redact(&Options::parse(args)?),
+        "" | "help" | "-h" | "--help" => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        command => Err(anyhow!("Unknown command {}\n\n{}", command, USAGE)),
+    }
+}
@@ -0,0 +1,249 @@
+#[cfg(feature = "gui")]
+use anyhow::{anyhow, Result};
+#[cfg(feature = "gui")]
+use crate::bill_reader::Transaction;
+#[cfg(feature = "gui")]
+use crate::query::transaction_date;
+
+//ISO codes recognised in front of a foreign amount at the end of a description
+pub const CURRENCY_CODES: &[&str] = &[
+    "AUD", "CAD", "CHF", "CNY", "DKK", "EUR", "GBP", "HKD", "IDR", "INR", "JPY", "KRW", "MYR",
+    "NOK", "NZD", "PHP", "SEK", "SGD", "THB", "TWD", "USD", "VND",
+];
+
+/// Amount in the currency the merchant charged, as printed on the statement line.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct ForeignAmount {
+    pub currency: String,
+    pub amount: f64,
+}
+
+#[cfg(feature = "gui")]
+/// One unit of `from` is worth `rate` units of `to`, from `date` ("2024-06-01") onwards when set.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct Rate {
+    pub from: String,
+    pub to: String,
+    pub rate: f64,
+    pub date: Option<String>,
+}
+
+#[cfg(feature = "gui")]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default)]
+pub struct RateTable {
+    pub rates: Vec<Rate>,
+}
+
+#[cfg(feature = "gui")]
+impl RateTable {
+    /// Reads `from,to,rate[,date]` lines, a header line is skipped.
+    pub fn from_csv(text: &str) -> Result<Self> {
+        let mut rates = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let fields = line.split(',').map(|f| f.trim().trim_matches('"')).collect::<Vec<&str>>();
+            if line.trim().is_empty() || (number == 0 && fields[0].eq_ignore_ascii_case("from")) {
+                continue;
+            }
+            let (from, to, rate, date) = match fields[..] {
+                [from, to, rate] => (from, to, rate, None),
+                [from, to, rate, date] => (from, to, rate, Some(date).filter(|d| !d.is_empty())),
+                _ => return Err(anyhow!("Line {}: expected from,to,rate[,date]", number + 1)),
+            };
+            let rate = rate.parse::<f64>()
+                .ok()
+                .filter(|r| *r > 0.0)
+                .ok_or_else(|| anyhow!("Line {}: \"{}\" is not a rate", number + 1, rate))?;
+            rates.push(Rate {
+                from: from.to_uppercase(),
+                to: to.to_uppercase(),
+                rate,
+                date: date.map(str::to_string),
+            });
+        }
+        Ok(Self { rates })
+    }
+
+    //latest rate in effect on `date`, undated rates apply to any date
+    fn direct(&self, from: &str, to: &str, date: Option<&str>) -> Option<f64> {
+        self.rates.iter()
+            .filter_map(|r| {
+                if r.from == from && r.to == to {
+                    Some((r.date.as_deref(), r.rate))
+                } else if r.from == to && r.to == from {
+                    Some((r.date.as_deref(), 1.0 / r.rate))
+                } else {
+                    None
+                }
+            })
+            .filter(|(d, _)| match (d, date) {
+                (Some(d), Some(date)) => *d <= date,
+                _ => true,
+            })
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, rate)| rate)
+    }
+
+    /// Rate between two currencies, either listed directly or through one shared currency.
+    pub fn rate(&self, from: &str, to: &str, date: Option<&str>) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
+        }
+        if let Some(rate) = self.direct(from, to, date) {
+            return Some(rate);
+        }
+        self.rates.iter()
+            .flat_map(|r| [r.from.as_str(), r.to.as_str()])
+            .filter(|via| *via != from && *via != to)
+            .find_map(|via| Some(self.direct(from, via, date)? * self.direct(via, to, date)?))
+    }
+}
+
+#[cfg(feature = "gui")]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct CurrencySettings {
+    /// Reporting currency, every total is converted to it.
+    pub home: String,
+    /// Currency of the statements opened from now on, empty when it is the home currency.
+    pub statement: String,
+    pub rates: RateTable,
+}
+
+#[cfg(feature = "gui")]
+impl Default for CurrencySettings {
+    fn default() -> Self {
+        Self {
+            home: "SGD".to_string(),
+            statement: String::new(),
+            rates: RateTable::default(),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl CurrencySettings {
+    /// Currency of `transaction.amount`.
+    pub fn currency_of<'a>(&'a self, transaction: &'a Transaction) -> &'a str {
+        if transaction.currency.is_empty() { &self.home } else { &transaction.currency }
+    }
+
+    /// Records the home currency on transactions stored before statements had one, so changing
+    /// the home currency later does not relabel them.
+    pub fn stamp(&self, transactions: &mut [Transaction]) {
+        for transaction in transactions.iter_mut().filter(|t| t.currency.is_empty()) {
+            transaction.currency = self.home.clone();
+        }
+    }
+
+    /// Amount in `to`, `None` when the rate table has no way to convert it.
+    pub fn amount_in(&self, transaction: &Transaction, to: &str, statement_month: Option<&str>) -> Option<f64> {
+        let from = self.currency_of(transaction);
+        if from == to {
+            return Some(transaction.amount);
+        }
+        let date = transaction_date(transaction, statement_month)
+            .map(|(year, month, day)| format!("{:04}-{:02}-{:02}", year, month, day));
+        self.rates.rate(from, to, date.as_deref())
+            .map(|rate| transaction.amount * rate)
+    }
+
+    /// Amount in the home currency, `None` when the rate table has no way to convert it.
+    pub fn home_amount(&self, transaction: &Transaction, statement_month: Option<&str>) -> Option<f64> {
+        self.amount_in(transaction, &self.home, statement_month)
+    }
+
+    /// Amount the table shows, in the home currency or in the statement currency when there is
+    /// no rate.
+    pub fn shown_amount(&self, transaction: &Transaction, statement_month: Option<&str>) -> f64 {
+        self.home_amount(transaction, statement_month).unwrap_or(transaction.amount)
+    }
+
+    /// Copies of `transactions` with their amount in `to`, for the summaries, reports and exports
+    /// which total them, and how many were left out for want of a rate.
+    pub fn convert(&self, transactions: &[Transaction], to: &str, statement_month: Option<&str>) -> (Vec<Transaction>, usize) {
+        let converted = transactions.iter()
+            .filter_map(|t| Some(Transaction {
+                amount: self.amount_in(t, to, statement_month)?,
+                currency: to.to_string(),
+                ..t.clone()
+            }))
+            .collect::<Vec<Transaction>>();
+        let missing = transactions.len() - converted.len();
+        (converted, missing)
+    }
+}
+
+#[cfg(all(test, feature = "gui"))]
+mod tests {
+    use super::*;
+
+    const RATES: &str = "from,to,rate,date\nUSD,SGD,1.30,\nEUR,USD,1.10,2024-06-01\nEUR,USD,1.20,2024-07-01\n";
+
+    fn table() -> RateTable {
+        RateTable::from_csv(RATES).unwrap()
+    }
+
+    fn close(actual: Option<f64>, expected: f64) -> bool {
+        actual.is_some_and(|a| (a - expected).abs() < 1e-9)
+    }
+
+    #[test]
+    fn rates_from_csv() {
+        let rates = table().rates;
+        assert_eq!(rates.len(), 3);
+        assert_eq!((rates[0].from.as_str(), rates[0].to.as_str(), rates[0].rate, rates[0].date.as_deref()), ("USD", "SGD", 1.3, None));
+        assert_eq!(rates[2].date.as_deref(), Some("2024-07-01"));
+        let quoted = RateTable::from_csv("\"usd\", \"sgd\", \"1.3\"\n\n").unwrap();
+        assert_eq!((quoted.rates[0].from.as_str(), quoted.rates[0].to.as_str()), ("USD", "SGD"));
+    }
+
+    #[test]
+    fn bad_csv_rows() {
+        let error = |text: &str| RateTable::from_csv(text).unwrap_err().to_string();
+        assert_eq!(error("from,to,rate\nUSD,SGD\n"), "Line 2: expected from,to,rate[,date]");
+        assert_eq!(error("USD,SGD,abc"), "Line 1: \"abc\" is not a rate");
+        assert_eq!(error("USD,SGD,0"), "Line 1: \"0\" is not a rate");
+        assert_eq!(error("USD,SGD,1.3,2024-06-01,extra"), "Line 1: expected from,to,rate[,date]");
+    }
+
+    #[test]
+    fn direct_rates() {
+        let table = table();
+        assert!(close(table.rate("USD", "SGD", None), 1.3));
+        assert!(close(table.rate("SGD", "USD", None), 1.0 / 1.3));
+        assert!(close(table.rate("SGD", "SGD", None), 1.0));
+        //the latest rate in effect on the date
+        assert!(close(table.rate("EUR", "USD", Some("2024-06-15")), 1.1));
+        assert!(close(table.rate("EUR", "USD", Some("2024-07-15")), 1.2));
+    }
+
+    #[test]
+    fn rates_through_another_currency() {
+        let table = table();
+        assert!(close(table.rate("EUR", "SGD", Some("2024-06-15")), 1.1 * 1.3));
+        assert!(close(table.rate("SGD", "EUR", Some("2024-07-15")), 1.0 / (1.2 * 1.3)));
+    }
+
+    #[test]
+    fn missing_rates() {
+        let table = table();
+        assert_eq!(table.rate("JPY", "SGD", None), None);
+        //no EUR rate is in effect yet
+        assert_eq!(table.rate("EUR", "USD", Some("2024-05-31")), None);
+        assert_eq!(RateTable::default().rate("USD", "SGD", None), None);
+    }
+
+    #[test]
+    fn conversions() {
+        let settings = CurrencySettings { rates: table(), ..Default::default() };
+        let mut usd = Transaction::new("05 JUN".to_string(), "AMAZON".to_string(), 10.0, String::new(), Vec::new());
+        usd.currency = "USD".to_string();
+        let mut jpy = usd.clone();
+        jpy.currency = "JPY".to_string();
+        assert!(close(settings.home_amount(&usd, Some("2024-06")), 13.0));
+        assert!(close(Some(settings.shown_amount(&jpy, Some("2024-06"))), 10.0));
+        let (converted, missing) = settings.convert(&[usd, jpy], "SGD", Some("2024-06"));
+        assert_eq!((converted.len(), missing), (1, 1));
+        assert_eq!(converted[0].currency, "SGD");
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::f32::consts::TAU;
+use egui::{Color32, Pos2, Sense, Shape, Stroke, Vec2};
+use egui::epaint::Hsva;
+use egui_plot::{Bar, BarChart, Legend, Line, Plot, PlotPoints};
+use crate::currency::CurrencySettings;
+use crate::history::StatementHistory;
+
+const TOP_MERCHANTS: usize = 10;
+const PLOT_HEIGHT: f32 = 220.0;
+const PIE_RADIUS: f32 = 90.0;
+
+/// Month and tag of the bar the user clicked, the table is filtered to it.
+pub struct Selection {
+    pub month: String,
+    pub tag: String,
+}
+
+//same golden ratio hue spread as egui_plot so the pie matches the bars
+fn color(index: usize) -> Color32 {
+    let golden_ratio = (5.0_f32.sqrt() - 1.0) / 2.0;
+    let h = index as f32 * golden_ratio;
+    Hsva::new(h, 0.85, 0.5, 1.0).into()
+}
+
+struct Totals<'a> {
+    months: Vec<&'a str>,
+    tags: Vec<String>,
+    //[tag][month]
+    per_tag: Vec<Vec<f64>>,
+    per_card: BTreeMap<String, Vec<f64>>,
+    merchants: Vec<(String, f64)>,
+}
+
+impl<'a> Totals<'a> {
+    /// Totals in the home currency, transactions without an exchange rate are left out.
+    fn new(history: &'a StatementHistory, currency: &CurrencySettings) -> Self {
+        //a month can have statements of several cards or banks
+        let mut months = history.statements.iter().map(|s| s.month.as_str()).collect::<Vec<&str>>();
+        months.dedup();
+        let tags = history.statements.iter()
+            .flat_map(|s| s.transactions.iter().flat_map(|t| t.tags.iter().cloned()))
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect::<Vec<String>>();
+
+        let mut per_tag = vec![vec![0f64; months.len()]; tags.len()];
+        let mut per_card = BTreeMap::<String, Vec<f64>>::new();
+        //card keys to the card as the first transaction prints it
+        let mut cards = BTreeMap::<String, String>::new();
+        let mut merchants = BTreeMap::<String, f64>::new();
+        for statement in &history.statements {
+            let Ok(month) = months.binary_search(&statement.month.as_str()) else {
+                continue;
+            };
+            for transaction in &statement.transactions {
+                let Some(amount) = currency.home_amount(transaction, Some(&statement.month)) else {
+                    continue;
+                };
+                for tag in &transaction.tags {
+                    if let Ok(index) = tags.binary_search(tag) {
+                        per_tag[index][month] += amount;
+                    }
+                }
+                let card = cards.entry(transaction.card_key()).or_insert_with(|| transaction.card.clone());
+                per_card.entry(card.clone())
+                    .or_insert_with(|| vec![0f64; months.len()])[month] += amount;
+                let merchant = if transaction.merchant.name.is_empty() {
+                    transaction.description.clone()
+                } else {
+                    transaction.merchant.name.clone()
+                };
+                *merchants.entry(merchant).or_default() += amount;
+            }
+        }
+
+        let mut merchants = merchants.into_iter().collect::<Vec<(String, f64)>>();
+        merchants.sort_by(|a, b| b.1.total_cmp(&a.1));
+        merchants.truncate(TOP_MERCHANTS);
+
+        Self { months, tags, per_tag, per_card, merchants }
+    }
+
+    fn month_label(&self, value: f64) -> String {
+        let index = value.round();
+        if (value - index).abs() > f64::EPSILON || index < 0.0 {
+            return String::new();
+        }
+        self.months.get(index as usize).map(|m| m.to_string()).unwrap_or_default()
+    }
+
+    //bars are stacked in tag order, so walk up the stack until the clicked height is covered
+    fn tag_at(&self, month: usize, y: f64) -> Option<&str> {
+        let mut top = 0f64;
+        for (tag, totals) in self.tags.iter().zip(&self.per_tag) {
+            let value = totals[month];
+            if value <= 0.0 {
+                continue;
+            }
+            if y >= top && y <= top + value {
+                return Some(tag);
+            }
+            top += value;
+        }
+        None
+    }
+}
+
+fn tag_chart(ui: &mut egui::Ui, totals: &Totals<'_>) -> Option<Selection> {
+    let mut charts: Vec<BarChart> = Vec::new();
+    for (index, (tag, values)) in totals.tags.iter().zip(&totals.per_tag).enumerate() {
+        let bars = values.iter()
+            .enumerate()
+            .map(|(month, value)| Bar::new(month as f64, *value).name(totals.months[month]))
+            .collect();
+        let chart = BarChart::new(bars)
+            .name(tag)
+            .color(color(index))
+            .width(0.6)
+            .stack_on(&charts.iter().collect::<Vec<&BarChart>>());
+        charts.push(chart);
+    }
+
+    let response = Plot::new("tag_chart")
+        .height(PLOT_HEIGHT)
+        .legend(Legend::default())
+        .allow_zoom(false)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .x_axis_formatter(|mark, _| totals.month_label(mark.value))
+        .show(ui, |plot_ui| {
+            for chart in charts {
+                plot_ui.bar_chart(chart);
+            }
+            plot_ui.pointer_coordinate()
+        });
+
+    if !response.response.clicked() {
+        return None;
+    }
+    let point = response.inner?;
+    let month = point.x.round();
+    if month < 0.0 || month as usize >= totals.months.len() {
+        return None;
+    }
+    let month = month as usize;
+    totals.tag_at(month, point.y).map(|tag| Selection {
+        month: totals.months[month].to_string(),
+        tag: tag.to_string(),
+    })
+}
+
+fn card_chart(ui: &mut egui::Ui, totals: &Totals<'_>) {
+    Plot::new("card_chart")
+        .height(PLOT_HEIGHT)
+        .legend(Legend::default())
+        .allow_zoom(false)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .x_axis_formatter(|mark, _| totals.month_label(mark.value))
+        .show(ui, |plot_ui| {
+            for (card, values) in &totals.per_card {
+                let points = values.iter()
+                    .enumerate()
+                    .map(|(month, value)| [month as f64, *value])
+                    .collect::<PlotPoints>();
+                plot_ui.line(Line::new(points).name(card));
+            }
+        });
+}
+
+fn tag_pie(ui: &mut egui::Ui, totals: &Totals<'_>) {
+    //refunds can leave a tag below zero, which has no place in a pie
+    let slices = totals.tags.iter()
+        .zip(&totals.per_tag)
+        .enumerate()
+        .map(|(index, (tag, values))| (index, tag, values.iter().sum::<f64>()))
+        .filter(|(_, _, total)| *total > 0.0)
+        .collect::<Vec<(usize, &String, f64)>>();
+    let sum = slices.iter().map(|(_, _, total)| total).sum::<f64>();
+    if sum <= 0.0 {
+        ui.label("No spending to show.");
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        let (response, painter) = ui.allocate_painter(Vec2::splat(PIE_RADIUS * 2.0), Sense::hover());
+        let center = response.rect.center();
+        let mut start = -TAU / 4.0;
+        for (index, _, total) in &slices {
+            let sweep = (*total / sum) as f32 * TAU;
+            let steps = ((sweep / TAU * 64.0).ceil() as usize).max(1);
+            let points = (0..=steps)
+                .map(|step| {
+                    let angle = start + sweep * step as f32 / steps as f32;
+                    center + Vec2::angled(angle) * PIE_RADIUS
+                })
+                .collect::<Vec<Pos2>>();
+            for pair in points.windows(2) {
+                painter.add(Shape::convex_polygon(vec![center, pair[0], pair[1]], color(*index), Stroke::NONE));
+            }
+            start += sweep;
+        }
+
+        ui.vertical(|ui| {
+            for (index, tag, total) in &slices {
+                ui.horizontal(|ui| {
+                    let (rect, _) = ui.allocate_exact_size(Vec2::splat(10.0), Sense::hover());
+                    ui.painter().rect_filled(rect, 2.0, color(*index));
+                    ui.label(format!("{} {:.2} ({:.0}%)", tag, total, total / sum * 100.0));
+                });
+            }
+        });
+    });
+}
+
+fn top_merchants(ui: &mut egui::Ui, totals: &Totals<'_>) {
+    let max = totals.merchants.first().map(|(_, total)| *total).unwrap_or_default();
+    egui::Grid::new("top_merchants_grid")
+        .striped(true)
+        .show(ui, |ui| {
+            for (merchant, total) in &totals.merchants {
+                ui.label(merchant);
+                ui.label(format!("{:.2}", total));
+                let fraction = if max > 0.0 { (*total / max).max(0.0) as f32 } else { 0.0 };
+                ui.add(egui::ProgressBar::new(fraction).desired_width(120.0));
+                ui.end_row();
+            }
+        });
+}
+
+/// Charts across every stored statement, returns the bar clicked in the monthly chart.
+pub fn show(ui: &mut egui::Ui, history: &StatementHistory, currency: &CurrencySettings) -> Option<Selection> {
+    if history.statements.is_empty() {
+        ui.label("Open a statement to fill the dashboard.");
+        return None;
+    }
+    let totals = Totals::new(history, currency);
+
+    ui.heading("Monthly totals per tag");
+    ui.label("Click a bar to list the transactions of that month and tag.");
+    let selection = tag_chart(ui, &totals);
+    ui.separator();
+
+    ui.heading("Per card");
+    card_chart(ui, &totals);
+    ui.separator();
+
+    ui.columns(2, |columns| {
+        columns[0].heading("Tags");
+        tag_pie(&mut columns[0], &totals);
+        columns[1].heading("Top merchants");
+        top_merchants(&mut columns[1], &totals);
+    });
+
+    selection
+}
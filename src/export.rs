@@ -0,0 +1,117 @@
+use anyhow::Result;
+use rust_xlsxwriter::{Format, Workbook};
+use crate::bill_reader::Transaction;
+use crate::summary::{SplitSettings, Summary};
+
+fn headers(settings: &SplitSettings) -> Vec<String> {
+    let mut headers = vec!["Date", "Description", "Merchant", "Amount", "Currency", "Card", "Tags"]
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<String>>();
+    headers.extend(settings.people.iter().map(|p| format!("{} share", p)));
+    headers.push("Notes".to_string());
+    headers.push("Receipts".to_string());
+    headers
+}
+
+fn share_of(shares: &[(String, f64)], person: &str) -> f64 {
+    shares.iter()
+        .filter(|(p, _)| p == person)
+        .map(|(_, s)| s)
+        .sum()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One row per transaction, tags and receipt names are joined with ";" to keep a single column.
+pub fn to_csv(transactions: &[Transaction], settings: &SplitSettings) -> String {
+    let mut csv = headers(settings).iter()
+        .map(|h| csv_field(h))
+        .collect::<Vec<String>>()
+        .join(",");
+    csv.push('\n');
+
+    for transaction in transactions {
+        let shares = settings.shares(transaction);
+        let mut fields = vec![
+            transaction.date.clone(),
+            transaction.description.clone(),
+            transaction.merchant.display(),
+            format!("{:.2}", transaction.amount),
+            transaction.currency.clone(),
+            transaction.card.clone(),
+            transaction.tags.join(";"),
+        ];
+        fields.extend(settings.people.iter().map(|p| format!("{:.2}", share_of(&shares, p))));
+        fields.push(transaction.notes.clone());
+        fields.push(transaction.attachments.iter().map(|a| a.file_name.as_str()).collect::<Vec<&str>>().join(";"));
+
+        csv.push_str(&fields.iter().map(|f| csv_field(f)).collect::<Vec<String>>().join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Workbook with a "Transactions" sheet matching the CSV and a "Summary" sheet with the totals
+/// per tag, card and person.
+pub fn to_xlsx(transactions: &[Transaction], settings: &SplitSettings) -> Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    let money = Format::new().set_num_format("#,##0.00");
+
+    let sheet = workbook.add_worksheet().set_name("Transactions")?;
+    for (column, header) in headers(settings).iter().enumerate() {
+        sheet.write_string_with_format(0, column as u16, header, &bold)?;
+    }
+    for (index, transaction) in transactions.iter().enumerate() {
+        let row = index as u32 + 1;
+        let shares = settings.shares(transaction);
+        sheet.write_string(row, 0, &transaction.date)?;
+        sheet.write_string(row, 1, &transaction.description)?;
+        sheet.write_string(row, 2, transaction.merchant.display())?;
+        sheet.write_number_with_format(row, 3, transaction.amount, &money)?;
+        sheet.write_string(row, 4, &transaction.currency)?;
+        sheet.write_string(row, 5, &transaction.card)?;
+        sheet.write_string(row, 6, transaction.tags.join(", "))?;
+        let mut column = 7u16;
+        for person in &settings.people {
+            sheet.write_number_with_format(row, column, share_of(&shares, person), &money)?;
+            column += 1;
+        }
+        sheet.write_string(row, column, &transaction.notes)?;
+        sheet.write_string(row, column + 1, transaction.attachments.iter().map(|a| a.file_name.as_str()).collect::<Vec<&str>>().join(", "))?;
+    }
+    sheet.autofit();
+
+    let summary = Summary::new(transactions, settings);
+    let sheet = workbook.add_worksheet().set_name("Summary")?;
+    let mut row = 0u32;
+    sheet.write_string_with_format(row, 0, "Transactions", &bold)?;
+    sheet.write_number(row, 1, summary.count as f64)?;
+    row += 1;
+    sheet.write_string_with_format(row, 0, "Total", &bold)?;
+    sheet.write_number_with_format(row, 1, summary.total, &money)?;
+    row += 2;
+
+    for (title, totals) in [("Tag", &summary.per_tag), ("Card", &summary.per_card), ("Person", &summary.per_person)] {
+        sheet.write_string_with_format(row, 0, title, &bold)?;
+        sheet.write_string_with_format(row, 1, "Total", &bold)?;
+        row += 1;
+        for (name, total) in totals {
+            sheet.write_string(row, 0, name)?;
+            sheet.write_number_with_format(row, 1, *total, &money)?;
+            row += 1;
+        }
+        row += 1;
+    }
+    sheet.autofit();
+
+    Ok(workbook.save_to_buffer()?)
+}
@@ -0,0 +1,104 @@
+use egui::{Color32, RichText};
+use crate::bill_reader::Transaction;
+use crate::query::Query;
+
+/// Style given to the table rows matching `condition`, which uses the search box syntax.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct FormatRule {
+    pub name: String,
+    pub enabled: bool,
+    pub condition: String,
+    pub text_color: Option<Color32>,
+    pub background: Option<Color32>,
+    pub bold: bool,
+    pub dim: bool,
+}
+
+impl Default for FormatRule {
+    fn default() -> Self {
+        Self {
+            name: "New rule".to_string(),
+            enabled: true,
+            condition: String::new(),
+            text_color: None,
+            background: None,
+            bold: false,
+            dim: false,
+        }
+    }
+}
+
+impl FormatRule {
+    /// Rules matching the look of the table before they were configurable, plus a few common ones.
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            //amounts which round to 50 or more to the nearest 10
+            Self { name: "Large amounts".to_string(), condition: "amount>=45".to_string(), bold: true, ..Default::default() },
+            Self { name: "Refunds".to_string(), condition: "amount<0".to_string(), text_color: Some(Color32::from_rgb(0, 150, 70)), ..Default::default() },
+            Self { name: "Untagged".to_string(), condition: "tag:none".to_string(), background: Some(Color32::from_rgba_unmultiplied(255, 200, 0, 40)), ..Default::default() },
+            Self { name: "Excluded".to_string(), condition: "tag:excluded".to_string(), dim: true, ..Default::default() },
+        ]
+    }
+}
+
+/// Combined style of every rule matching a row, later rules win on colours.
+#[derive(Default)]
+pub struct RowFormat {
+    pub text_color: Option<Color32>,
+    pub background: Option<Color32>,
+    pub bold: bool,
+    pub dim: bool,
+}
+
+impl RowFormat {
+    pub fn text(&self, text: impl Into<String>) -> RichText {
+        let mut text = RichText::new(text);
+        if let Some(color) = self.text_color {
+            text = text.color(if self.dim { color.gamma_multiply(0.5) } else { color });
+        } else if self.dim {
+            text = text.weak();
+        }
+        if self.bold {
+            text = text.strong();
+        }
+        text
+    }
+
+    /// Paints the row background behind the cell content, call before adding the content.
+    pub fn paint_background(&self, ui: &egui::Ui) {
+        if let Some(background) = self.background {
+            ui.painter().rect_filled(ui.max_rect(), 0.0, background);
+        }
+    }
+}
+
+/// Rules with their condition parsed once per frame, rules with an invalid condition are skipped.
+pub struct Formatter<'a> {
+    rules: Vec<(Query, &'a FormatRule)>,
+}
+
+impl<'a> Formatter<'a> {
+    pub fn new(rules: &'a [FormatRule]) -> Self {
+        Self {
+            rules: rules.iter()
+                .filter(|r| r.enabled && !r.condition.trim().is_empty())
+                .filter_map(|r| Query::parse(&r.condition).ok().map(|q| (q, r)))
+                .collect(),
+        }
+    }
+
+    /// Format of the row of `transaction`, `amount` is the amount the table shows.
+    pub fn format(&self, transaction: &Transaction, amount: f64, statement_month: Option<&str>) -> RowFormat {
+        let mut format = RowFormat::default();
+        for (query, rule) in &self.rules {
+            if !query.matches_amount(transaction, amount, statement_month) {
+                continue;
+            }
+            format.text_color = rule.text_color.or(format.text_color);
+            format.background = rule.background.or(format.background);
+            format.bold |= rule.bold;
+            format.dim |= rule.dim;
+        }
+        format
+    }
+}
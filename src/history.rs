@@ -0,0 +1,127 @@
+use chrono::Datelike;
+#[cfg(feature = "gui")]
+use crate::attachment::Attachment;
+use crate::bill_reader::Transaction;
+
+#[cfg(feature = "gui")]
+/// Which statement a history entry is: the "2024-06" billing month, the profile which read it
+/// and its card ids, so statements of other cards or banks for the same month are kept apart.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(default)]
+pub struct StatementKey {
+    pub month: String,
+    pub profile: String,
+    pub cards: Vec<String>,
+}
+
+#[cfg(feature = "gui")]
+impl StatementKey {
+    /// Key of a statement of `transactions` read by `profile`, `year` is the one printed on the
+    /// statement when the profile finds it.
+    pub fn new(transactions: &[Transaction], profile: &str, year: Option<i32>) -> Option<Self> {
+        let mut cards = transactions.iter()
+            .map(Transaction::card_key)
+            .collect::<Vec<String>>();
+        cards.sort();
+        cards.dedup();
+        Some(Self {
+            month: statement_month(transactions, year)?,
+            profile: profile.to_string(),
+            cards,
+        })
+    }
+
+    /// Year of the billing month.
+    pub fn year(&self) -> Option<i32> {
+        self.month.split('-').next()?.parse().ok()
+    }
+
+    /// "2024-06 Citibank 5136, 0005", the cards by their last digits.
+    pub fn label(&self) -> String {
+        let cards = self.cards.iter()
+            .map(|c| c.chars().skip(c.chars().count().saturating_sub(4)).collect::<String>())
+            .collect::<Vec<String>>()
+            .join(", ");
+        [self.month.as_str(), self.profile.as_str(), cards.as_str()].iter()
+            .filter(|p| !p.is_empty())
+            .copied()
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+}
+
+#[cfg(feature = "gui")]
+/// Statement kept after another bill is opened, with the fields of its [`StatementKey`].
+/// Statements stored before the key had a profile and cards only have the month.
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub struct Statement {
+    pub month: String,
+    #[serde(default)]
+    pub profile: String,
+    #[serde(default)]
+    pub cards: Vec<String>,
+    pub transactions: Vec<Transaction>,
+}
+
+#[cfg(feature = "gui")]
+impl Statement {
+    pub fn key(&self) -> StatementKey {
+        StatementKey { month: self.month.clone(), profile: self.profile.clone(), cards: self.cards.clone() }
+    }
+}
+
+#[cfg(feature = "gui")]
+/// Every statement opened so far, sorted by key so the statements of a month are together.
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(default)]
+pub struct StatementHistory {
+    pub statements: Vec<Statement>,
+}
+
+#[cfg(feature = "gui")]
+impl StatementHistory {
+    /// Adds the statement, or replaces the one already stored with the same key.
+    pub fn store(&mut self, key: &StatementKey, transactions: &[Transaction]) {
+        if transactions.is_empty() {
+            return;
+        }
+        let statement = Statement {
+            month: key.month.clone(),
+            profile: key.profile.clone(),
+            cards: key.cards.clone(),
+            transactions: transactions.to_vec(),
+        };
+        match self.statements.binary_search_by(|s| s.key().cmp(key)) {
+            Ok(index) => self.statements[index] = statement,
+            Err(index) => self.statements.insert(index, statement),
+        }
+    }
+
+    pub fn get(&self, key: &StatementKey) -> Option<&Statement> {
+        self.statements.iter().find(|s| s.key() == *key)
+    }
+
+    /// First statement of the "2024-06" `month`.
+    pub fn first_of_month(&self, month: &str) -> Option<&Statement> {
+        self.statements.iter().find(|s| s.month == month)
+    }
+
+    /// Whether a statement other than the open one, stored as `open`, has `attachment`.
+    pub fn uses_attachment(&self, attachment: &Attachment, open: Option<&StatementKey>) -> bool {
+        self.statements.iter()
+            .filter(|s| open != Some(&s.key()))
+            .flat_map(|s| &s.transactions)
+            .any(|t| t.attachments.contains(attachment))
+    }
+}
+
+/// Billing month of a statement, taken from its last transaction. The year is the one printed
+/// on the statement, or when there is none the latest month which is not in the future.
+pub fn statement_month(transactions: &[Transaction], year: Option<i32>) -> Option<String> {
+    let (_, month) = transactions.last()?.day_month()?;
+    let year = year.unwrap_or_else(|| {
+        let today = chrono::Local::now();
+        if month > today.month() { today.year() - 1 } else { today.year() }
+    });
+    Some(format!("{:04}-{:02}", year, month))
+}
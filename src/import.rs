@@ -0,0 +1,225 @@
+use anyhow::{anyhow, Result};
+use log::{log, Level};
+use crate::amount::AmountFormat;
+use crate::attachment::{self, Attachment};
+use crate::bill_reader::{BillReader, CreditCardBillReader, PlainTextExtractor, ReaderOptions, Statement, StatementParser, TextExtractor, Transaction};
+
+/// Outcome of one file of a batch import, shown in the import results window.
+//the command line only reports the error
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+#[derive(Clone, Debug)]
+pub struct ImportResult {
+    pub file_name: String,
+    pub count: usize,
+    pub error: Option<String>,
+}
+
+//rows of fields, quoted fields may hold commas, quotes ("") and line breaks
+fn records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    records
+}
+
+/// Reads a CSV with a header line, such as the one written by File > Export > CSV. Date,
+/// Description and Amount columns are required, Card, Tags (";" separated) and Notes are optional.
+pub fn from_csv(text: &str) -> Result<Vec<Transaction>> {
+    let records = records(text.trim_start_matches('\u{feff}'));
+    let Some((header, rows)) = records.split_first() else {
+        return Err(anyhow!("The file is empty"));
+    };
+    let column = |name: &str| header.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+    let required = |name: &str| column(name).ok_or_else(|| anyhow!("Missing \"{}\" column", name));
+    let (date, description, amount) = (required("Date")?, required("Description")?, required("Amount")?);
+    let (card, tags, notes) = (column("Card"), column("Tags"), column("Notes"));
+
+    rows.iter()
+        .enumerate()
+        .map(|(number, row)| {
+            let field = |index: Option<usize>| index.and_then(|i| row.get(i)).map(|f| f.trim()).unwrap_or_default();
+            let amount_text = field(Some(amount));
+            let value = AmountFormat::default().parse(amount_text)
+                .map_err(|e| anyhow!("Line {}: {}", number + 2, e))?;
+            let mut transaction = Transaction::new(
+                field(Some(date)).to_string(),
+                field(Some(description)).to_string(),
+                value,
+                field(card).to_string(),
+                field(tags).split(';').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect());
+            transaction.notes = field(notes).to_string();
+            Ok(transaction)
+        })
+        .collect()
+}
+
+/// A statement read from a PDF or a text sample, when it has transactions.
+fn check_statement(statement: Statement) -> Result<Statement> {
+    match statement {
+        Statement { pages: 0, .. } => Err(anyhow!("The statement could not be read")),
+        statement if statement.profile.is_empty() => Err(anyhow!("No statement profile matches this statement")),
+        Statement { transactions, .. } if transactions.is_empty() => Err(anyhow!("No transactions found")),
+        statement => Ok(statement),
+    }
+}
+
+/// Saves a PDF statement so the statement viewer can show the line each transaction came from.
+/// It is saved before the reader takes the data, a file which turns out not to be a statement
+/// stays in the store, where reading it again reuses it as the id is its content hash.
+async fn keep_document(file_name: &str, data: &[u8]) -> Option<Attachment> {
+    let document = Attachment::new(file_name.to_string(), data);
+    match attachment::save(&document, data).await {
+        Ok(()) => Some(document),
+        Err(e) => {
+            log!(Level::Warn, "Unable to keep {} for the statement viewer: {}", file_name, e);
+            None
+        }
+    }
+}
+
+/// Reads a PDF statement, a text sample or a CSV file, picked by the file extension. The reader
+/// is only created for the first PDF, the other files do not need pdfium. `None` when
+/// `progress` cancelled it.
+async fn read_file(reader: &mut Option<CreditCardBillReader>, options: &ReaderOptions, file_name: &str, data: Vec<u8>, keep_statement: bool, progress: &mut impl FnMut(usize, usize) -> bool) -> Option<Result<Statement>> {
+    let extension = file_name.rsplit_once('.')
+        .map(|(_, e)| e.to_lowercase())
+        .unwrap_or_default();
+    if extension == "pdf" && reader.is_none() {
+        match CreditCardBillReader::new(options.clone()) {
+            Ok(r) => *reader = Some(r),
+            Err(e) => return Some(Err(anyhow!("Unable to read PDFs: {}", e))),
+        }
+    }
+    let read = match (extension.as_str(), reader.as_ref()) {
+        ("pdf", Some(reader)) => {
+            let document = if keep_statement { keep_document(file_name, &data).await } else { None };
+            reader.read(data, &mut *progress).await?.and_then(check_statement).map(|mut statement| {
+                for source in statement.transactions.iter_mut().filter_map(|t| t.source.as_mut()) {
+                    source.statement = document.clone();
+                }
+                statement
+            })
+        }
+        ("txt", _) => match PlainTextExtractor.extract(data, &mut *progress).await? {
+            Ok(pages) => check_statement(StatementParser::new(options.clone()).parse(&pages)),
+            Err(e) => Err(e),
+        },
+        ("csv", _) => from_csv(&String::from_utf8_lossy(&data))
+            .map(|transactions| Statement { transactions, ..Default::default() }),
+        _ => Err(anyhow!("Unsupported file type, use PDF, CSV or a text sample")),
+    };
+    progress(1, 1).then_some(read)
+}
+
+/// Which file of the batch is being read and how far into it.
+//the command line reads one file at a time without a progress bar
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+#[derive(Clone, Debug, Default)]
+pub struct Progress {
+    pub file_name: String,
+    pub file: usize,
+    pub files: usize,
+    pub page: usize,
+    pub pages: usize,
+}
+
+/// Runs every file through the reader, the statements of the files which could be read come
+/// back in file order. Files which could not be loaded carry their error instead of their data.
+/// With `keep_statements` the PDFs go to the attachment store for the statement viewer. `None`
+/// when `progress` returned false to cancel.
+pub async fn read_files(files: Vec<(String, Result<Vec<u8>, String>)>, options: &ReaderOptions, keep_statements: bool, mut progress: impl FnMut(&Progress) -> bool) -> Option<(Vec<Statement>, Vec<ImportResult>)> {
+    let mut reader = None;
+    let mut statements = Vec::new();
+    let mut results = Vec::new();
+    let files_count = files.len();
+    for (index, (file_name, data)) in files.into_iter().enumerate() {
+        let mut current = Progress { file_name: file_name.clone(), file: index + 1, files: files_count, page: 0, pages: 0 };
+        if !progress(&current) {
+            return None;
+        }
+        let read = match data {
+            Ok(data) => read_file(&mut reader, options, &file_name, data, keep_statements, &mut |page, pages| {
+                current.page = page;
+                current.pages = pages;
+                progress(&current)
+            }).await?,
+            Err(e) => Err(anyhow!(e)),
+        };
+        match read {
+            Ok(statement) => {
+                results.push(ImportResult { file_name, count: statement.transactions.len(), error: None });
+                statements.push(statement);
+            }
+            Err(e) => results.push(ImportResult { file_name, count: 0, error: Some(e.to_string()) }),
+        }
+    }
+    Some((statements, results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv_error(text: &str) -> String {
+        from_csv(text).err().map(|e| e.to_string()).unwrap_or_default()
+    }
+
+    #[test]
+    fn csv_with_quoted_fields() {
+        let text = "\u{feff}Date,Description,Amount,Card,Tags,Notes\r\n05 JUN,\"GRAB, SINGAPORE\",12.50,1234 5678 9012 5136,Joint,\"Said \"\"hi\"\"\nto Sam\"\r\n";
+        let transactions = from_csv(text).unwrap();
+        assert_eq!(transactions.len(), 1);
+        let transaction = &transactions[0];
+        assert_eq!(transaction.date, "05 JUN");
+        assert_eq!(transaction.description, "GRAB, SINGAPORE");
+        assert_eq!(transaction.amount, 12.5);
+        assert_eq!(transaction.card, "1234 5678 9012 5136");
+        assert_eq!(transaction.notes, "Said \"hi\"\nto Sam");
+    }
+
+    #[test]
+    fn csv_tags_are_separated_by_semicolons() {
+        let text = "Description,Tags,Date,Amount\nGRAB, Joint ; Travel;;,05 JUN,(3.85)\nIKEA,,06 JUN,\"1,234.00\"\n";
+        let transactions = from_csv(text).unwrap();
+        assert_eq!(transactions[0].tags, vec!["Joint", "Travel"]);
+        assert_eq!(transactions[0].amount, -3.85);
+        assert!(transactions[1].tags.is_empty());
+        assert_eq!(transactions[1].amount, 1234.0);
+        assert_eq!(transactions[1].card, "");
+    }
+
+    #[test]
+    fn csv_without_a_required_column() {
+        assert_eq!(csv_error("Date,Description,Card\n05 JUN,GRAB,5136\n"), "Missing \"Amount\" column");
+        assert_eq!(csv_error(""), "The file is empty");
+    }
+
+    #[test]
+    fn csv_with_a_bad_amount() {
+        let text = "Date,Description,Amount\n05 JUN,GRAB,12.50\n06 JUN,IKEA,twelve\n";
+        assert_eq!(csv_error(text), "Line 3: \"twelve\" is not an amount, \"t\" is not expected");
+    }
+}
@@ -0,0 +1,362 @@
+use std::sync::{Arc, Mutex};
+use egui::{Color32, RichText, Window};
+use pdfium_render::prelude::{PdfDocument, PdfPage, PdfPageAnnotationCommon};
+use crate::bill_reader::{self, CreditCardBillReader, LineKind, ReaderOptions, TextLine};
+use crate::profile::Profile;
+use crate::redact;
+
+const CARD_COLOR: Color32 = Color32::from_rgb(70, 140, 230);
+const TRANSACTION_COLOR: Color32 = Color32::from_rgb(60, 170, 90);
+
+/// File name and content of the statement to inspect, filled in by the app's file dialog.
+pub type Picked = Arc<Mutex<Option<(String, Vec<u8>)>>>;
+
+struct PageInfo {
+    label: Option<String>,
+    width: f32,
+    height: f32,
+    paper_size: String,
+    boundaries: Vec<String>,
+    links: Vec<String>,
+    annotations: Vec<String>,
+    lines: Vec<(String, LineKind)>,
+}
+
+/// What pdfium reports about a statement, the same details the old console dump logged.
+struct Inspection {
+    file_name: String,
+    version: String,
+    //name of the profile whose patterns colour the lines
+    profile: Option<String>,
+    metadata: Vec<(String, String)>,
+    form: Option<(String, Vec<(String, String)>)>,
+    pages: Vec<PageInfo>,
+}
+
+impl Inspection {
+    fn new(reader: &CreditCardBillReader, file_name: String, document: &PdfDocument<'_>) -> Self {
+        let pages = document.pages();
+        let first_page = pages.iter().next()
+            .and_then(|p| p.text().ok().map(|t| bill_reader::page_lines(&t).into_iter().map(|l| l.text).collect::<Vec<String>>()))
+            .unwrap_or_default();
+        let profile = reader.parser().profile(&first_page);
+        let form = document.form().map(|form| {
+            let mut fields = form.field_values(pages).into_iter()
+                .map(|(key, value)| (key, value.unwrap_or_default()))
+                .collect::<Vec<(String, String)>>();
+            fields.sort();
+            (format!("{:?}", form.form_type()), fields)
+        });
+        Self {
+            file_name,
+            version: format!("{:?}", document.version()),
+            profile: profile.map(|p| p.name.clone()),
+            metadata: document.metadata().iter()
+                .map(|tag| (format!("{:?}", tag.tag_type()), tag.value().to_string()))
+                .collect(),
+            form,
+            pages: pages.iter().map(|page| Self::page(profile, &page)).collect(),
+        }
+    }
+
+    fn page(profile: Option<&Profile>, page: &PdfPage<'_>) -> PageInfo {
+        let text = page.text().ok();
+        let boundaries = page.boundaries().iter()
+            .map(|b| format!("{:?} ({}, {}) - ({}, {})", b.box_type,
+                b.bounds.left.value, b.bounds.top.value, b.bounds.right.value, b.bounds.bottom.value))
+            .collect();
+        let links = page.links().iter()
+            .map(|link| match link.action() {
+                Some(action) => match action.as_uri_action().and_then(|uri| uri.uri().ok()) {
+                    Some(uri) => format!("{:?} {}", action.action_type(), uri),
+                    None => format!("{:?}", action.action_type()),
+                },
+                None => "No action".to_string(),
+            })
+            .collect();
+        let annotations = page.annotations().iter()
+            .map(|annotation| {
+                let content = text.as_ref()
+                    .and_then(|t| t.for_annotation(&annotation).ok())
+                    .unwrap_or_default();
+                format!("{:?} {:?}: {:?}", annotation.annotation_type(), annotation.bounds().ok(), content)
+            })
+            .collect();
+        let lines = text.as_ref()
+            .map(|t| bill_reader::page_lines(t).into_iter()
+                .map(|TextLine { text: line, .. }| {
+                    let kind = profile.map(|p| p.line_kind(&line)).unwrap_or(LineKind::Other);
+                    (line, kind)
+                })
+                .collect())
+            .unwrap_or_default();
+        PageInfo {
+            label: page.label().map(str::to_string),
+            width: page.width().value,
+            height: page.height().value,
+            paper_size: format!("{:?}", page.paper_size()),
+            boundaries,
+            links,
+            annotations,
+            lines,
+        }
+    }
+}
+
+/// What the inspector needs the app for.
+pub enum Request {
+    OpenFile,
+    /// Save the anonymised sample text under the file name.
+    SaveSample(String, String),
+}
+
+/// Numbered monospace statement line coloured by the pattern it matches.
+pub fn line_text(number: usize, line: &str, kind: LineKind) -> RichText {
+    let text = RichText::new(format!("{:>3} {}", number, line)).monospace();
+    match kind {
+        LineKind::Card => text.color(CARD_COLOR),
+        LineKind::Transaction => text.color(TRANSACTION_COLOR),
+        LineKind::Ignored => text.weak().strikethrough(),
+        LineKind::Other => text.weak(),
+    }
+}
+
+/// Colours used by [`line_text`].
+pub fn build_legend(ui: &mut egui::Ui) {
+    ui.horizontal(|ui| {
+        ui.colored_label(CARD_COLOR, "Card");
+        ui.colored_label(TRANSACTION_COLOR, "Transaction");
+        ui.label(RichText::new("Ignored").weak().strikethrough());
+        ui.weak("No match");
+    });
+}
+
+/// "Inspect statement" window: document details and the raw text of each page, with lines
+/// coloured by the pattern they match, for working out the patterns of a new statement layout.
+#[derive(Default)]
+pub struct StatementInspector {
+    open: bool,
+    options: ReaderOptions,
+    reader: Option<CreditCardBillReader>,
+    picked: Picked,
+    inspection: Option<Result<Inspection, String>>,
+    page: usize,
+    //anonymised text of the inspected statement, edited before it is saved
+    sample: Option<String>,
+}
+
+impl StatementInspector {
+    /// Opens the window and hands out the slot the picked statement goes in, the statement is
+    /// read with the profiles of `options`.
+    pub fn pick(&mut self, options: ReaderOptions) -> Picked {
+        self.open = true;
+        if options.profiles != self.options.profiles {
+            self.reader = None;
+        }
+        self.options = options;
+        self.picked = Arc::default();
+        Arc::clone(&self.picked)
+    }
+
+    fn inspect(&mut self, file_name: String, data: Vec<u8>) {
+        self.page = 0;
+        self.sample = None;
+        if self.reader.is_none() {
+            match CreditCardBillReader::new(self.options.clone()) {
+                Ok(reader) => self.reader = Some(reader),
+                Err(e) => {
+                    self.inspection = Some(Err(e.to_string()));
+                    return;
+                }
+            }
+        }
+        let Some(reader) = &self.reader else {
+            return;
+        };
+        let Some(pdfium) = reader.extractor().pdfium() else {
+            self.inspection = Some(Err("Inspecting statements needs the pdfium library, `billsplit redact` saves a sample without it".to_string()));
+            return;
+        };
+        self.inspection = Some(match pdfium.load_pdf_from_byte_vec(data, None) {
+            Ok(document) => Ok(Inspection::new(reader, file_name, &document)),
+            Err(e) => Err(format!("{}: {}", file_name, e)),
+        });
+    }
+
+    /// Statement text with the private details replaced, read with the profile which matched.
+    fn anonymise(&self) -> Option<String> {
+        let (Some(reader), Some(Ok(inspection))) = (&self.reader, &self.inspection) else {
+            return None;
+        };
+        let pages = inspection.pages.iter()
+            .map(|p| p.lines.iter().map(|(l, _)| l.clone()).collect())
+            .collect::<Vec<Vec<String>>>();
+        let profile = reader.parser().profile(pages.first().map(Vec::as_slice).unwrap_or_default())?;
+        Some(redact::anonymise(profile, &pages))
+    }
+
+    fn build_sample_window(&mut self, ctx: &egui::Context) -> Option<Request> {
+        let Some(sample) = &mut self.sample else {
+            return None;
+        };
+        let mut open = true;
+        let mut save = false;
+        Window::new("Anonymised sample")
+            .open(&mut open)
+            .default_size([560.0, 420.0])
+            .show(ctx, |ui| {
+                ui.label("Card numbers, merchants and amounts are replaced. Words on lines which match no pattern, such as your name and address, are kept: check them and edit them out before sending the sample.");
+                ui.horizontal(|ui| {
+                    save = ui.button("Save...").clicked();
+                });
+                egui::ScrollArea::vertical()
+                    .id_source("inspector_sample")
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        ui.add(egui::TextEdit::multiline(sample)
+                            .code_editor()
+                            .desired_width(f32::INFINITY));
+                    });
+            });
+        let request = save.then(|| {
+            let file_name = match &self.inspection {
+                Some(Ok(inspection)) => inspection.file_name.rsplit_once('.').map(|(n, _)| n).unwrap_or(&inspection.file_name),
+                _ => "statement",
+            };
+            Request::SaveSample(format!("{}-sample.txt", file_name), sample.clone())
+        });
+        if !open {
+            self.sample = None;
+        }
+        request
+    }
+
+    fn build_list(ui: &mut egui::Ui, title: &str, items: &[String]) {
+        if items.is_empty() {
+            ui.label(format!("No {}", title.to_lowercase()));
+            return;
+        }
+        egui::CollapsingHeader::new(format!("{} ({})", title, items.len()))
+            .show(ui, |ui| {
+                for item in items {
+                    ui.label(item);
+                }
+            });
+    }
+
+    fn build_page(ui: &mut egui::Ui, page: &PageInfo) {
+        let label = page.label.as_deref().map(|l| format!(", label {}", l)).unwrap_or_default();
+        ui.label(format!("{} x {} points, {}{}", page.width, page.height, page.paper_size, label));
+        Self::build_list(ui, "Boundaries", &page.boundaries);
+        Self::build_list(ui, "Links", &page.links);
+        Self::build_list(ui, "Annotations", &page.annotations);
+        ui.separator();
+
+        build_legend(ui);
+        egui::ScrollArea::vertical()
+            .id_source("inspector_text")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for (number, (line, kind)) in page.lines.iter().enumerate() {
+                    ui.label(line_text(number + 1, line, *kind));
+                }
+            });
+    }
+
+    /// Inspects a statement once it has been picked.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Request> {
+        let picked = self.picked.lock().unwrap().take();
+        if let Some((file_name, data)) = picked {
+            self.inspect(file_name, data);
+        }
+        if !self.open {
+            return None;
+        }
+
+        let mut request = self.build_sample_window(ctx);
+        let mut anonymise = false;
+        let mut open = self.open;
+        Window::new("Inspect statement")
+            .open(&mut open)
+            .default_size([640.0, 480.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Open PDF...").clicked() {
+                        request = Some(Request::OpenFile);
+                    }
+                    if let Some(Ok(inspection)) = &self.inspection {
+                        anonymise = ui.add_enabled(inspection.profile.is_some(), egui::Button::new("Anonymised sample..."))
+                            .on_hover_text("Statement text without private details, for reporting a statement which is not read right")
+                            .on_disabled_hover_text("No statement profile matches this statement")
+                            .clicked();
+                        ui.strong(&inspection.file_name);
+                    }
+                });
+                ui.separator();
+                let inspection = match &self.inspection {
+                    None => {
+                        ui.label("Open a PDF statement to see what pdfium reads from it.");
+                        return;
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                        return;
+                    }
+                    Some(Ok(inspection)) => inspection,
+                };
+
+                ui.label(format!("PDF version {}", inspection.version));
+                match &inspection.profile {
+                    Some(profile) => ui.label(format!("Read with the {} profile", profile)),
+                    None => ui.colored_label(ui.visuals().error_fg_color, "No statement profile matches the first page"),
+                };
+                egui::CollapsingHeader::new(format!("Metadata ({})", inspection.metadata.len()))
+                    .show(ui, |ui| {
+                        egui::Grid::new("inspector_metadata").striped(true).show(ui, |ui| {
+                            for (tag, value) in &inspection.metadata {
+                                ui.label(tag);
+                                ui.label(value);
+                                ui.end_row();
+                            }
+                        });
+                    });
+                match &inspection.form {
+                    Some((form_type, fields)) => {
+                        egui::CollapsingHeader::new(format!("{} form ({} fields)", form_type, fields.len()))
+                            .show(ui, |ui| {
+                                egui::Grid::new("inspector_form").striped(true).show(ui, |ui| {
+                                    for (key, value) in fields {
+                                        ui.label(key);
+                                        ui.label(value);
+                                        ui.end_row();
+                                    }
+                                });
+                            });
+                    }
+                    None => {
+                        ui.label("No embedded form");
+                    }
+                }
+                ui.separator();
+
+                if inspection.pages.is_empty() {
+                    ui.label("No pages");
+                    return;
+                }
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Page:");
+                    for index in 0..inspection.pages.len() {
+                        ui.selectable_value(&mut self.page, index, (index + 1).to_string());
+                    }
+                });
+                if let Some(page) = inspection.pages.get(self.page) {
+                    Self::build_page(ui, page);
+                }
+            });
+        self.open = open;
+        if anonymise {
+            self.sample = self.anonymise();
+        }
+        request
+    }
+}
@@ -0,0 +1,122 @@
+//! JavaScript API of the wasm build. Pdfium has to be initialised first the way index.html does
+//! it, with `initialize_pdfium_render(pdfiumModule, wasmBindings, false)`.
+
+use wasm_bindgen::prelude::*;
+use crate::bill_reader::{BillReader, CreditCardBillReader, ReaderOptions, Statement, StatementProfile, Transaction};
+use crate::history;
+use crate::merchant::MerchantNormaliser;
+use crate::rules::{self, TagRule};
+
+//keys are renamed to snake_case on the way in, see `from_js`
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+struct Options {
+    personal_cards: Option<Vec<String>>,
+    profiles: Vec<StatementProfile>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(default)]
+struct Diagnostics {
+    pages: usize,
+    profile: String,
+    cards: Vec<String>,
+    unmatched_lines: Vec<String>,
+}
+
+/// What `parseStatement` returns and `applyRules` takes back.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct JsStatement {
+    transactions: Vec<Transaction>,
+    #[serde(default)]
+    diagnostics: Diagnostics,
+}
+
+//"card_id" and "cardId", object keys are camelCase on the JS side and snake_case in the Rust types
+fn rename_keys(value: serde_json::Value, rename: &impl Fn(&str) -> String) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => map.into_iter()
+            .map(|(key, value)| (rename(&key), rename_keys(value, rename)))
+            .collect(),
+        serde_json::Value::Array(values) => values.into_iter().map(|v| rename_keys(v, rename)).collect(),
+        value => value,
+    }
+}
+
+fn camel_case(key: &str) -> String {
+    let mut parts = key.split('_');
+    let first = parts.next().unwrap_or_default().to_string();
+    parts.fold(first, |mut name, part| {
+        let mut chars = part.chars();
+        if let Some(c) = chars.next() {
+            name.extend(c.to_uppercase());
+            name.push_str(chars.as_str());
+        }
+        name
+    })
+}
+
+fn snake_case(key: &str) -> String {
+    key.chars()
+        .flat_map(|c| if c.is_uppercase() { vec!['_', c.to_ascii_lowercase()] } else { vec![c] })
+        .collect()
+}
+
+//JS values go through JSON so the serde derives of the Rust types are reused
+fn from_js<T: serde::de::DeserializeOwned + Default>(value: &JsValue, name: &str) -> Result<T, JsError> {
+    if value.is_undefined() || value.is_null() {
+        return Ok(T::default());
+    }
+    let json = js_sys::JSON::stringify(value)
+        .map_err(|_| JsError::new(&format!("{} is not JSON serialisable", name)))?;
+    let value = serde_json::from_str::<serde_json::Value>(&String::from(json))
+        .map_err(|e| JsError::new(&format!("Invalid {}: {}", name, e)))?;
+    serde_json::from_value(rename_keys(value, &snake_case))
+        .map_err(|e| JsError::new(&format!("Invalid {}: {}", name, e)))
+}
+
+fn to_js(statement: &JsStatement) -> Result<JsValue, JsError> {
+    let value = serde_json::to_value(statement).map_err(|e| JsError::new(&e.to_string()))?;
+    let json = rename_keys(value, &camel_case).to_string();
+    js_sys::JSON::parse(&json).map_err(|_| JsError::new("Unable to build the result"))
+}
+
+/// `parseStatement(bytes, { personalCards: ["5136"], profiles: [...] })` resolves to
+/// `{ transactions, diagnostics: { pages, profile, cards, unmatchedLines } }`. Every object key
+/// is camelCase, such as `cardId` and `cardHeader`.
+#[wasm_bindgen(js_name = parseStatement)]
+pub async fn parse_statement(bytes: Vec<u8>, options: JsValue) -> Result<JsValue, JsError> {
+    let options = from_js::<Options>(&options, "options")?;
+    let mut reader_options = ReaderOptions::default();
+    if let Some(personal_cards) = options.personal_cards {
+        reader_options.personal_cards = personal_cards;
+    }
+    for profile in &options.profiles {
+        profile.compile().map_err(|e| JsError::new(&e))?;
+    }
+    reader_options.profiles.splice(0..0, options.profiles);
+    let reader = CreditCardBillReader::new(reader_options)
+        .map_err(|e| JsError::new(&format!("Pdfium is not initialised: {}", e)))?;
+
+    let Statement { mut transactions, pages, cards, unmatched_lines, profile, .. } = reader.read(bytes, |_, _| true).await
+        .unwrap_or_else(|| Ok(Statement::default()))
+        .map_err(|e| JsError::new(&format!("Unable to read the statement: {}", e)))?;
+    MerchantNormaliser::new(&[]).apply(&mut transactions);
+    to_js(&JsStatement {
+        transactions,
+        diagnostics: Diagnostics { pages, profile, cards, unmatched_lines },
+    })
+}
+
+/// `applyRules(statement, [{ condition: "desc:grab", tag: "Joint" }])` returns the statement with
+/// each transaction tagged by the first rule it matches, conditions use the search box syntax.
+#[wasm_bindgen(js_name = applyRules)]
+pub fn apply_rules(statement: JsValue, rules: JsValue) -> Result<JsValue, JsError> {
+    let mut statement = from_js::<Option<JsStatement>>(&statement, "statement")?
+        .ok_or_else(|| JsError::new("statement is required"))?;
+    let rules = from_js::<Vec<TagRule>>(&rules, "rules")?;
+    let month = history::statement_month(&statement.transactions, None);
+    rules::apply(&rules, &mut statement.transactions, month.as_deref())
+        .map_err(|e| JsError::new(&e))?;
+    to_js(&statement)
+}
@@ -0,0 +1,300 @@
+use std::collections::BTreeSet;
+use anyhow::{anyhow, Result};
+use chrono::Datelike;
+use crate::bill_reader::Transaction;
+use crate::summary::SplitSettings;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum LedgerFormat {
+    Ledger,
+    HLedger,
+    Beancount,
+}
+
+impl LedgerFormat {
+    pub const ALL: [LedgerFormat; 3] = [LedgerFormat::Ledger, LedgerFormat::HLedger, LedgerFormat::Beancount];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LedgerFormat::Ledger => "Ledger",
+            LedgerFormat::HLedger => "hledger",
+            LedgerFormat::Beancount => "Beancount",
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            LedgerFormat::Ledger => "statement.ledger",
+            LedgerFormat::HLedger => "statement.journal",
+            LedgerFormat::Beancount => "statement.beancount",
+        }
+    }
+}
+
+/// Maps a tag, or the last digits of a card number, to an account.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default, Debug)]
+pub struct AccountMapping {
+    pub key: String,
+    pub account: String,
+}
+
+/// Accounts used by the plain-text accounting exports. The payer's share of a transaction is
+/// posted to the tag account and everyone else's share to `receivable_account:<Person>`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct LedgerSettings {
+    pub currency: String,
+    pub tag_accounts: Vec<AccountMapping>,
+    pub card_accounts: Vec<AccountMapping>,
+    pub expense_account: String,
+    pub card_account: String,
+    pub receivable_account: String,
+    //statements only print day and month, the app sets it from the open statement
+    #[serde(skip)]
+    pub year: i32,
+}
+
+impl Default for LedgerSettings {
+    fn default() -> Self {
+        Self {
+            currency: "SGD".to_string(),
+            tag_accounts: Vec::new(),
+            card_accounts: Vec::new(),
+            expense_account: "Expenses:Unknown".to_string(),
+            card_account: "Liabilities:CreditCard".to_string(),
+            receivable_account: "Assets:Receivable".to_string(),
+            year: chrono::Local::now().year(),
+        }
+    }
+}
+
+impl LedgerSettings {
+    fn tag_account(&self, transaction: &Transaction) -> String {
+        transaction.tags.iter()
+            .find_map(|t| self.tag_accounts.iter().find(|m| !m.key.is_empty() && &m.key == t))
+            .map(|m| m.account.clone())
+            .unwrap_or_else(|| self.expense_account.clone())
+    }
+
+    fn card_account(&self, transaction: &Transaction) -> String {
+        self.card_accounts.iter()
+            .find(|m| !m.key.is_empty() && transaction.card_ends_with(&m.key))
+            .map(|m| m.account.clone())
+            .unwrap_or_else(|| self.card_account.clone())
+    }
+}
+
+struct Entry<'a> {
+    transaction: &'a Transaction,
+    date: String,
+    postings: Vec<(String, i64)>,
+}
+
+//"05 JUN" -> (2024, 6, 5)
+fn full_date(transaction: &Transaction, year: i32, last_month: u32) -> Result<(i32, u32, u32)> {
+    let Some((day, month)) = transaction.day_month() else {
+        return Err(anyhow!("Cannot read the date \"{}\"", transaction.date));
+    };
+    //a January statement still lists the December transactions of the year before
+    let year = if month > last_month { year - 1 } else { year };
+    Ok((year, month, day))
+}
+
+fn format_cents(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, cents.abs() / 100, cents.abs() % 100)
+}
+
+//beancount account components must start with a capital letter and cannot contain spaces
+fn beancount_account(account: &str) -> String {
+    account.split(':')
+        .map(|component| {
+            let component = component.chars()
+                .map(|c| if c.is_alphanumeric() { c } else { '-' })
+                .collect::<String>();
+            let mut chars = component.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn entries<'a>(transactions: &'a [Transaction], split: &SplitSettings, settings: &LedgerSettings) -> Result<Vec<Entry<'a>>> {
+    let last_month = transactions.last()
+        .and_then(|t| t.day_month())
+        .map(|(_, month)| month)
+        .unwrap_or(12);
+
+    transactions.iter()
+        .map(|transaction| {
+            let (year, month, day) = full_date(transaction, settings.year, last_month)?;
+            let cents = (transaction.amount * 100.0).round() as i64;
+
+            let shares = split.shares(transaction);
+            let mut postings = if shares.is_empty() {
+                vec![(settings.tag_account(transaction), cents)]
+            } else {
+                shares.iter()
+                    .enumerate()
+                    .map(|(index, (person, share))| {
+                        let account = if index == 0 {
+                            settings.tag_account(transaction)
+                        } else {
+                            format!("{}:{}", settings.receivable_account, person)
+                        };
+                        (account, (share * 100.0).round() as i64)
+                    })
+                    .collect()
+            };
+            postings.push((settings.card_account(transaction), -cents));
+
+            //the shares add up to the amount in cents, so the entry always balances
+            if postings.iter().any(|(account, _)| account.trim().is_empty()) {
+                return Err(anyhow!("{} {} has a posting without an account", transaction.date, transaction.description));
+            }
+            Ok(Entry {
+                transaction,
+                date: format!("{:04}-{:02}-{:02}", year, month, day),
+                postings,
+            })
+        })
+        .collect()
+}
+
+/// Journal with one balanced entry per transaction, fails on the first transaction without a
+/// date or an account so a broken file is never written.
+pub fn to_journal(transactions: &[Transaction], split: &SplitSettings, settings: &LedgerSettings, format: LedgerFormat) -> Result<String> {
+    let entries = entries(transactions, split, settings)?;
+    let mut journal = String::new();
+
+    if format == LedgerFormat::Beancount {
+        journal.push_str(&format!("option \"operating_currency\" {}\n\n", quote(&settings.currency)));
+        if let Some(first) = entries.iter().map(|e| &e.date).min() {
+            let accounts = entries.iter()
+                .flat_map(|e| e.postings.iter().map(|(account, _)| beancount_account(account)))
+                .collect::<BTreeSet<String>>();
+            for account in accounts {
+                journal.push_str(&format!("{} open {}\n", first, account));
+            }
+            journal.push('\n');
+        }
+    }
+
+    for entry in entries {
+        let transaction = entry.transaction;
+        let notes = transaction.notes.lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<&str>>();
+        match format {
+            LedgerFormat::Ledger | LedgerFormat::HLedger => {
+                let date = if format == LedgerFormat::Ledger { entry.date.replace('-', "/") } else { entry.date.clone() };
+                journal.push_str(&format!("{} {}\n", date, transaction.description));
+                if !transaction.tags.is_empty() {
+                    let tags = if format == LedgerFormat::Ledger {
+                        format!(":{}:", transaction.tags.join(":"))
+                    } else {
+                        transaction.tags.iter().map(|t| format!("{}:", t)).collect::<Vec<String>>().join(", ")
+                    };
+                    journal.push_str(&format!("    ; {}\n", tags));
+                }
+                for note in notes {
+                    journal.push_str(&format!("    ; {}\n", note));
+                }
+                for (account, cents) in entry.postings {
+                    journal.push_str(&format!("    {:<40}  {} {}\n", account, format_cents(cents), settings.currency));
+                }
+            }
+            LedgerFormat::Beancount => {
+                let tags = transaction.tags.iter()
+                    .map(|t| format!(" #{}", t.replace(' ', "-")))
+                    .collect::<String>();
+                journal.push_str(&format!("{} * {} {}{}\n", entry.date, quote(&transaction.merchant.name), quote(&transaction.description), tags));
+                if !notes.is_empty() {
+                    journal.push_str(&format!("  note: {}\n", quote(&notes.join(" "))));
+                }
+                for (account, cents) in entry.postings {
+                    journal.push_str(&format!("  {:<40}  {} {}\n", beancount_account(&account), format_cents(cents), settings.currency));
+                }
+            }
+        }
+        journal.push('\n');
+    }
+
+    Ok(journal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bill_reader::JOINT_TAG;
+
+    fn transaction(date: &str, amount: f64, tags: &[&str]) -> Transaction {
+        let tags = tags.iter().map(|t| t.to_string()).collect();
+        Transaction::new(date.to_string(), "GRAB".to_string(), amount, "1234 5678 9012 5136".to_string(), tags)
+    }
+
+    fn settings() -> LedgerSettings {
+        LedgerSettings {
+            tag_accounts: vec![AccountMapping { key: JOINT_TAG.to_string(), account: "Expenses:Shared".to_string() }],
+            card_accounts: vec![AccountMapping { key: "5136".to_string(), account: "Liabilities:Citi".to_string() }],
+            year: 2025,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn shared_items_are_split_between_postings() {
+        let transactions = [transaction("05 JUN", 10.01, &[JOINT_TAG])];
+        let split = SplitSettings { people: vec!["Me".to_string(), "Partner".to_string(), "Kid".to_string()] };
+        let entries = entries(&transactions, &split, &settings()).unwrap();
+        assert_eq!(entries[0].postings, vec![
+            ("Expenses:Shared".to_string(), 334),
+            ("Assets:Receivable:Partner".to_string(), 334),
+            ("Assets:Receivable:Kid".to_string(), 333),
+            ("Liabilities:Citi".to_string(), -1001),
+        ]);
+    }
+
+    #[test]
+    fn untagged_and_unmapped_transactions_use_the_default_accounts() {
+        let mut transaction = transaction("05 JUN", 12.5, &[]);
+        transaction.card = "4321 8765 2109 0005".to_string();
+        let entries = entries(std::slice::from_ref(&transaction), &SplitSettings::default(), &settings()).unwrap();
+        assert_eq!(entries[0].postings, vec![
+            ("Expenses:Unknown".to_string(), 1250),
+            ("Liabilities:CreditCard".to_string(), -1250),
+        ]);
+    }
+
+    #[test]
+    fn december_transactions_of_a_january_statement_are_in_the_year_before() {
+        let transactions = [transaction("28 DEC", 1.0, &[]), transaction("03 JAN", 2.0, &[])];
+        let dates = entries(&transactions, &SplitSettings::default(), &settings()).unwrap()
+            .into_iter()
+            .map(|e| e.date)
+            .collect::<Vec<String>>();
+        assert_eq!(dates, vec!["2024-12-28", "2025-01-03"]);
+    }
+
+    #[test]
+    fn bad_dates_are_errors() {
+        let transactions = [transaction("JUN 05", 1.0, &[])];
+        assert!(entries(&transactions, &SplitSettings::default(), &settings()).is_err());
+    }
+
+    #[test]
+    fn beancount_accounts() {
+        assert_eq!(beancount_account("Expenses:food & drinks"), "Expenses:Food---drinks");
+        assert_eq!(beancount_account("assets:receivable:Mary Ann"), "Assets:Receivable:Mary-Ann");
+        assert_eq!(beancount_account("Expenses:caf\u{e9}"), "Expenses:Caf\u{e9}");
+    }
+}
@@ -1,6 +1,85 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+//! Credit card statement reader and bill splitter.
+//!
+//! [`bill_reader`] is the stable API for reading statements without the app:
+//!
+//! ```no_run
+//! use credit_card_billsplit::bill_reader::{BillReader, CreditCardBillReader, ReaderOptions};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let options = ReaderOptions { personal_cards: vec!["5136".to_string()], ..Default::default() };
+//! let reader = CreditCardBillReader::new(options)?;
+//! let statement = reader.read_blocking(std::fs::read("statement.pdf")?)?;
+//! for transaction in &statement.transactions {
+//!     println!("{}", transaction);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Parsing does not need pdfium, text such as a test fixture goes straight to the parser:
+//!
+//! ```
+//! use credit_card_billsplit::bill_reader::{PlainTextExtractor, ReaderOptions, StatementParser};
+//!
+//! let text = "CITI REWARDS CARD 1234 5678 9012 5136 - J DOE\n05 JUN GRAB Singapore SG (3.85)";
+//! let statement = StatementParser::new(ReaderOptions::default()).parse(&PlainTextExtractor::pages(text));
+//! assert_eq!(statement.transactions[0].amount, -3.85);
+//! ```
+//!
+//! The egui app is behind the default `gui` feature, build with `default-features = false` to
+//! leave out eframe.
+
+//modules behind `any(feature = "gui", not(target_arch = "wasm32"))` serve the app and the
+//command line, the headless web build only has the JS API
+mod amount;
+#[cfg(feature = "gui")]
 mod app;
-mod bill_reader;
+mod attachment;
+#[cfg(feature = "gui")]
+mod background;
+pub mod bill_reader;
+#[cfg(feature = "gui")]
+mod budget;
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+mod currency;
+#[cfg(feature = "gui")]
+mod dashboard;
+#[cfg(any(feature = "gui", not(target_arch = "wasm32")))]
+mod export;
+#[cfg(feature = "gui")]
+mod formatting;
+mod history;
+#[cfg(any(feature = "gui", not(target_arch = "wasm32")))]
+mod import;
+#[cfg(feature = "gui")]
+mod inspector;
+#[cfg(target_arch = "wasm32")]
+mod js;
+#[cfg(any(feature = "gui", not(target_arch = "wasm32")))]
+mod ledger;
+mod merchant;
+#[cfg(feature = "gui")]
+mod palette;
+mod profile;
+mod query;
+#[cfg(any(feature = "gui", not(target_arch = "wasm32")))]
+mod redact;
+#[cfg(any(feature = "gui", not(target_arch = "wasm32")))]
+mod report;
+mod rules;
+#[cfg(any(feature = "gui", not(target_arch = "wasm32")))]
+mod share;
+#[cfg(feature = "gui")]
+mod shortcuts;
+#[cfg(any(feature = "gui", not(target_arch = "wasm32")))]
+mod summary;
+#[cfg(feature = "gui")]
+mod viewer;
 
+#[cfg(feature = "gui")]
 pub use app::BillSplitApp;
+#[cfg(not(target_arch = "wasm32"))]
+pub use cli::run_cli;
@@ -0,0 +1,246 @@
+use regex::{Regex, RegexBuilder};
+use crate::bill_reader::Transaction;
+
+/// Clean merchant details derived from a raw statement description.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default, Debug, PartialEq)]
+pub struct Merchant {
+    pub name: String,
+    pub city: Option<String>,
+    pub country: Option<String>,
+}
+
+impl Merchant {
+    /// "Grab (Singapore, SG)" style label used in the table.
+    pub fn display(&self) -> String {
+        let location = [self.city.as_deref(), self.country.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<&str>>()
+            .join(", ");
+        if location.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} ({})", self.name, location)
+        }
+    }
+}
+
+/// User editable alias, `pattern` is a case-insensitive regex matched against the raw description.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default, Debug)]
+pub struct MerchantAlias {
+    pub pattern: String,
+    pub name: String,
+}
+
+//(pattern, clean name), checked after the user aliases
+const BUILT_IN_MERCHANTS: &[(&str, &str)] = &[
+    (r"^GRAB\s*\*?\s*(FOOD|MART)", "GrabFood"),
+    (r"^GRAB", "Grab"),
+    (r"^GOJEK", "Gojek"),
+    (r"^(AMZN|AMAZON)", "Amazon"),
+    (r"^NETFLIX", "Netflix"),
+    (r"^SPOTIFY", "Spotify"),
+    (r"^APPLE\.COM", "Apple"),
+    (r"^GOOGLE", "Google"),
+    (r"^SHOPEE", "Shopee"),
+    (r"^LAZADA", "Lazada"),
+    (r"^(BUS/MRT|TRANSITLINK)", "TransitLink"),
+    (r"^NTUC|^FAIRPRICE", "FairPrice"),
+    (r"^COLD STORAGE", "Cold Storage"),
+    (r"^IKEA", "IKEA"),
+    (r"^UNIQLO", "Uniqlo"),
+    (r"^SINGTEL", "Singtel"),
+    (r"^SP DIGITAL|^SP SERVICES", "SP Services"),
+];
+
+//payment processor prefixes which hide the real merchant name
+const PROCESSOR_PREFIXES: &[&str] = &[
+    r"^PAYPAL\s*\*\s*",
+    r"^SQ\s*\*\s*",
+    r"^SUMUP\s*\*\s*",
+    r"^STRIPE\s*\*\s*",
+    r"^ZETTLE_\*\s*",
+];
+
+//cities with a space in their name, matched before falling back to a single word
+const MULTI_WORD_CITIES: &[&str] = &[
+    "KUALA LUMPUR",
+    "HONG KONG",
+    "NEW YORK",
+    "SAN FRANCISCO",
+    "LOS ANGELES",
+    "JOHOR BAHRU",
+    "HO CHI MINH",
+];
+
+//last words which are part of the merchant name rather than a city
+const COMPANY_SUFFIXES: &[&str] = &["PTE", "LTD", "INC", "CO", "LLC", "BHD", "SDN"];
+
+pub struct MerchantNormaliser {
+    aliases: Vec<(Regex, String)>,
+    built_in: Vec<(Regex, String)>,
+    prefixes: Vec<Regex>,
+    country_regex: Regex,
+    reference_regex: Regex,
+}
+
+impl MerchantNormaliser {
+    /// Aliases with an invalid pattern are skipped so a half typed regex does not break the table.
+    pub fn new(aliases: &[MerchantAlias]) -> Self {
+        let build = |pattern: &str| RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build();
+
+        Self {
+            aliases: aliases.iter()
+                .filter(|a| !a.pattern.is_empty())
+                .filter_map(|a| build(&a.pattern).ok().map(|r| (r, a.name.clone())))
+                .collect(),
+            built_in: BUILT_IN_MERCHANTS.iter()
+                .map(|(p, n)| (build(p).unwrap(), n.to_string()))
+                .collect(),
+            prefixes: PROCESSOR_PREFIXES.iter()
+                .map(|p| build(p).unwrap())
+                .collect(),
+            //GRAB* A-2K9XYZ Singapore SG
+            country_regex: Regex::new(r"^(.*?)\s+([A-Z]{2})$").unwrap(),
+            //A-2K9XYZ, #1234, 000123456
+            reference_regex: Regex::new(r"^[#*]*[A-Z0-9-]*\d[A-Z0-9-]*$").unwrap(),
+        }
+    }
+
+    pub fn normalise(&self, description: &str) -> Merchant {
+        let description = description.split_whitespace().collect::<Vec<&str>>().join(" ");
+        let (rest, city, country) = self.split_location(&description);
+
+        let mut rest = rest.to_string();
+        for prefix in &self.prefixes {
+            rest = prefix.replace(&rest, "").to_string();
+        }
+
+        let name = self.aliases.iter()
+            .chain(self.built_in.iter())
+            .find(|(r, _)| r.is_match(&description) || r.is_match(&rest))
+            .map(|(_, n)| n.clone())
+            .unwrap_or_else(|| self.clean_name(&rest));
+
+        Merchant {
+            name: if name.is_empty() { description.clone() } else { name },
+            city,
+            country,
+        }
+    }
+
+    pub fn apply(&self, transactions: &mut [Transaction]) {
+        for transaction in transactions {
+            transaction.merchant = self.normalise(&transaction.description);
+        }
+    }
+
+    /// Merchant part of a description, then the city and country at its end.
+    pub(crate) fn split_location<'a>(&self, description: &'a str) -> (&'a str, Option<String>, Option<String>) {
+        let Some(c) = self.country_regex.captures(description) else {
+            return (description, None, None);
+        };
+        let head = c.get(1).unwrap().as_str();
+        let country = c.get(2).unwrap().as_str().to_string();
+
+        //compared on head's own bytes, uppercasing can change the length of other characters
+        let multi_word_city = MULTI_WORD_CITIES.iter().find_map(|city| {
+            let start = head.len().checked_sub(city.len() + 1)?;
+            (head.is_char_boundary(start) && head[start..].eq_ignore_ascii_case(&format!(" {}", city)))
+                .then_some((start, city))
+        });
+        if let Some((start, city)) = multi_word_city {
+            return (head[..start].trim_end(), Some(title_case(city)), Some(country));
+        }
+
+        match head.rsplit_once(' ') {
+            Some((merchant, city)) if city.chars().all(|ch| ch.is_alphabetic())
+                && !COMPANY_SUFFIXES.contains(&city.to_uppercase().as_str()) => {
+                (merchant, Some(title_case(city)), Some(country))
+            }
+            _ => (head, None, Some(country)),
+        }
+    }
+
+    fn clean_name(&self, rest: &str) -> String {
+        let words = rest.split([' ', '*'])
+            .filter(|w| !w.is_empty())
+            .filter(|w| !self.reference_regex.is_match(w))
+            .collect::<Vec<&str>>();
+        title_case(&words.join(" "))
+    }
+}
+
+impl Default for MerchantNormaliser {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+fn title_case(text: &str) -> String {
+    text.split(' ')
+        .map(|w| {
+            let mut chars = w.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merchant(name: &str, city: Option<&str>, country: Option<&str>) -> Merchant {
+        Merchant { name: name.to_string(), city: city.map(str::to_string), country: country.map(str::to_string) }
+    }
+
+    #[test]
+    fn aggregator_prefixes() {
+        let normaliser = MerchantNormaliser::default();
+        assert_eq!(normaliser.normalise("GRAB* A-2K9XYZ SINGAPORE SG"), merchant("Grab", Some("Singapore"), Some("SG")));
+        assert_eq!(normaliser.normalise("GRAB*FOOD A-2K9XYZ Singapore SG"), merchant("GrabFood", Some("Singapore"), Some("SG")));
+        assert_eq!(normaliser.normalise("PAYPAL *STEAMGAMES 4029357733 US"), merchant("Steamgames", None, Some("US")));
+    }
+
+    #[test]
+    fn multi_word_cities() {
+        let normaliser = MerchantNormaliser::default();
+        assert_eq!(normaliser.normalise("STARBUCKS KLCC KUALA LUMPUR MY"), merchant("Starbucks Klcc", Some("Kuala Lumpur"), Some("MY")));
+        assert_eq!(normaliser.normalise("Apple Store   hong kong HK"), merchant("Apple Store", Some("Hong Kong"), Some("HK")));
+    }
+
+    #[test]
+    fn multi_word_cities_after_characters_which_change_length() {
+        //"ſ" uppercases to the shorter "S", so the uppercased text matches "LOS ANGELES"
+        let normaliser = MerchantNormaliser::default();
+        assert_eq!(normaliser.split_location("CAF\u{131} LO\u{17f} ANGELE\u{17f} US"),
+            ("CAF\u{131} LO\u{17f}", Some("Angele\u{17f}".to_string()), Some("US".to_string())));
+        assert_eq!(normaliser.split_location("CAF\u{131} NEW YORK US"),
+            ("CAF\u{131}", Some("New York".to_string()), Some("US".to_string())));
+    }
+
+    #[test]
+    fn company_suffixes() {
+        let normaliser = MerchantNormaliser::default();
+        assert_eq!(normaliser.normalise("ACME TRADING PTE LTD SG"), merchant("Acme Trading Pte Ltd", None, Some("SG")));
+        assert_eq!(normaliser.normalise("WIDGETS INC US"), merchant("Widgets Inc", None, Some("US")));
+    }
+
+    #[test]
+    fn aliases() {
+        let normaliser = MerchantNormaliser::new(&[
+            MerchantAlias { pattern: "^acme".to_string(), name: "Acme Corp".to_string() },
+            MerchantAlias { pattern: "^grab".to_string(), name: "Taxi".to_string() },
+            MerchantAlias { pattern: "(".to_string(), name: "Broken".to_string() },
+        ]);
+        assert_eq!(normaliser.normalise("ACME TRADING PTE LTD SG").name, "Acme Corp");
+        assert_eq!(normaliser.normalise("GRAB* A-2K9XYZ SINGAPORE SG").name, "Taxi");
+        assert_eq!(normaliser.normalise("COLD STORAGE Singapore SG").name, "Cold Storage");
+    }
+}
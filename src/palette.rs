@@ -0,0 +1,140 @@
+use egui::{Key, TextEdit};
+use crate::ledger::LedgerFormat;
+
+/// Action run from the command palette.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    OpenBill,
+    ExportCsv,
+    ExportXlsx,
+    ExportJournal(LedgerFormat),
+    GenerateReport,
+    CopySummary,
+    CopySnapshotLink,
+    ApplyMerchantAliases,
+    ShowStatement,
+    ShowDashboard,
+    Settings,
+    MerchantAliases,
+    Budgets,
+    KeyboardShortcuts,
+    StatementViewer,
+    InspectStatement,
+    StatementProfiles,
+}
+
+impl Command {
+    pub fn all() -> Vec<Command> {
+        let mut commands = vec![Command::OpenBill, Command::ExportCsv, Command::ExportXlsx];
+        commands.extend(LedgerFormat::ALL.map(Command::ExportJournal));
+        commands.extend([
+            Command::GenerateReport,
+            Command::CopySummary,
+            Command::CopySnapshotLink,
+            Command::ApplyMerchantAliases,
+            Command::ShowStatement,
+            Command::ShowDashboard,
+            Command::Settings,
+            Command::MerchantAliases,
+            Command::Budgets,
+            Command::KeyboardShortcuts,
+            Command::StatementViewer,
+            Command::InspectStatement,
+            Command::StatementProfiles,
+        ]);
+        commands
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Command::OpenBill => "Open bill".to_string(),
+            Command::ExportCsv => "Export CSV".to_string(),
+            Command::ExportXlsx => "Export Excel (XLSX)".to_string(),
+            Command::ExportJournal(format) => format!("Export {}", format.name()),
+            Command::GenerateReport => "Generate report".to_string(),
+            Command::CopySummary => "Copy summary text".to_string(),
+            Command::CopySnapshotLink => "Copy snapshot link".to_string(),
+            Command::ApplyMerchantAliases => "Apply merchant aliases".to_string(),
+            Command::ShowStatement => "Show statement".to_string(),
+            Command::ShowDashboard => "Show dashboard".to_string(),
+            Command::Settings => "Settings".to_string(),
+            Command::MerchantAliases => "Merchant aliases".to_string(),
+            Command::Budgets => "Budgets".to_string(),
+            Command::KeyboardShortcuts => "Keyboard shortcuts".to_string(),
+            Command::StatementViewer => "Toggle statement viewer".to_string(),
+            Command::InspectStatement => "Inspect statement".to_string(),
+            Command::StatementProfiles => "Statement profiles".to_string(),
+        }
+    }
+}
+
+/// Ctrl+K list of commands filtered by what is typed, arrows pick one and Enter runs it.
+#[derive(Default)]
+pub struct Palette {
+    pub open: bool,
+    query: String,
+    index: usize,
+}
+
+impl Palette {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.index = 0;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Command> {
+        if !self.open {
+            return None;
+        }
+
+        let words = self.query.to_lowercase();
+        let words = words.split_whitespace().collect::<Vec<&str>>();
+        let commands = Command::all().into_iter()
+            .filter(|c| {
+                let name = c.name().to_lowercase();
+                words.iter().all(|w| name.contains(w))
+            })
+            .collect::<Vec<Command>>();
+        self.index = self.index.min(commands.len().saturating_sub(1));
+
+        let (up, down, enter, escape) = ctx.input_mut(|i| (
+            i.consume_key(egui::Modifiers::NONE, Key::ArrowUp),
+            i.consume_key(egui::Modifiers::NONE, Key::ArrowDown),
+            i.consume_key(egui::Modifiers::NONE, Key::Enter),
+            i.consume_key(egui::Modifiers::NONE, Key::Escape),
+        ));
+        if up {
+            self.index = self.index.saturating_sub(1);
+        }
+        if down && self.index + 1 < commands.len() {
+            self.index += 1;
+        }
+
+        let mut chosen = enter.then(|| commands.get(self.index).copied()).flatten();
+        egui::Window::new("Command palette")
+            .title_bar(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 40.0])
+            .show(ctx, |ui| {
+                let response = ui.add(TextEdit::singleline(&mut self.query)
+                    .hint_text("Type a command")
+                    .desired_width(300.0));
+                response.request_focus();
+                ui.separator();
+                for (index, command) in commands.iter().enumerate() {
+                    if ui.selectable_label(index == self.index, command.name()).clicked() {
+                        chosen = Some(*command);
+                    }
+                }
+                if commands.is_empty() {
+                    ui.weak("No matching command");
+                }
+            });
+
+        if chosen.is_some() || escape {
+            self.toggle();
+        }
+        chosen
+    }
+}
@@ -0,0 +1,292 @@
+use std::ops::Range;
+use regex::{Regex, RegexBuilder};
+use crate::amount::AmountFormat;
+use crate::bill_reader::{CardNumber, LineKind, MONTHS};
+use crate::currency::CURRENCY_CODES;
+
+/// Layout of one bank's statements, loaded from JSON so new banks need no code. Patterns are
+/// case-insensitive regexes.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct StatementProfile {
+    pub name: String,
+    /// Text found on the first page of this bank's statements, any one of them picks the profile.
+    /// A profile without fingerprints takes every statement, so it belongs last.
+    pub fingerprints: Vec<String>,
+    /// Line starting a card section, the `card` group (or the first group) is the card number. It
+    /// may use "X", "*" or "•" for masked digits, as long as the last four are shown.
+    pub card_header: String,
+    /// Transaction line with the groups `date`, `description` and `amount`.
+    pub transaction: String,
+    pub amount: AmountFormat,
+    /// How `date` is printed using DD, MM, MMM, YY and YYYY, such as "DD/MM/YYYY".
+    pub date_format: String,
+    /// Lines skipped before the other patterns are tried, such as page footers.
+    pub ignore: Vec<String>,
+    /// Line on the first page with the year of the statement, the `year` group (or the first
+    /// group) is the year. Empty when transactions take the latest year which is not in the future.
+    pub statement_year: String,
+}
+
+impl Default for StatementProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            fingerprints: Vec::new(),
+            card_header: String::new(),
+            transaction: String::new(),
+            amount: AmountFormat::default(),
+            date_format: "DD MMM".to_string(),
+            ignore: Vec::new(),
+            statement_year: String::new(),
+        }
+    }
+}
+
+impl StatementProfile {
+    /// The Citibank layout the reader was written for, used when no other profile matches.
+    pub fn citibank() -> Self {
+        Self {
+            name: "Citibank".to_string(),
+            //**** ************ CARD **** **** **** **** - *** *** ****, or **** ****** ***** for Amex,
+            //the groups may be separated by dashes
+            card_header: r".* CARD (?P<card>[0-9x*\u{2022}]{4}(?:[ -][0-9x*\u{2022}]{4,6}){2,3}) - .*".to_string(),
+            //05 JUN ********** Singapore SG (3.85), or 3.85 CR, -3.85 or 3.85-
+            //Statement Date June 12, 2024
+            statement_year: r"statement date\D*(?:\d{1,2}\D+){0,2}(?P<year>(?:19|20)\d{2})\b".to_string(),
+            transaction: r"(?P<date>\d{2} [a-z]{3}) (?P<description>.*) (?P<amount>\(?-?[0-9,]*\.\d{2}\)?(?:\s*(?:CR|DR))?-?)".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Profiles in a JSON file holding either one profile or a list of them.
+    pub fn from_json(text: &str) -> Result<Vec<StatementProfile>, String> {
+        //the first character picks the shape, so the error is about the field which is wrong
+        let profiles = if text.trim_start().starts_with('[') {
+            serde_json::from_str::<Vec<StatementProfile>>(text)
+        } else {
+            serde_json::from_str::<StatementProfile>(text).map(|p| vec![p])
+        };
+        let profiles = profiles.map_err(|e| e.to_string())?;
+        for profile in &profiles {
+            profile.compile()?;
+        }
+        Ok(profiles)
+    }
+
+    /// Checks the patterns, the error names the profile and the pattern which is wrong.
+    pub(crate) fn compile(&self) -> Result<Profile, String> {
+        let name = if self.name.is_empty() { "Unnamed profile" } else { self.name.as_str() };
+        let pattern = |field: &str, pattern: &str| RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("{} {}: {}", name, field, e));
+
+        let transaction = pattern("transaction", &self.transaction)?;
+        for group in ["date", "description", "amount"] {
+            if !transaction.capture_names().any(|n| n == Some(group)) {
+                return Err(format!("{} transaction: no \"{}\" group", name, group));
+            }
+        }
+        if self.card_header.is_empty() {
+            return Err(format!("{} card_header: no pattern", name));
+        }
+        if !self.date_format.contains("DD") || !self.date_format.contains("MM") {
+            return Err(format!("{} date_format: \"{}\" needs DD and MM or MMM", name, self.date_format));
+        }
+        if ![',', '.'].contains(&self.amount.decimal_separator) {
+            return Err(format!("{} amount: decimal_separator \"{}\" is not \".\" or \",\"", name, self.amount.decimal_separator));
+        }
+        Ok(Profile {
+            name: name.to_string(),
+            fingerprints: self.fingerprints.iter().map(|f| f.to_lowercase()).filter(|f| !f.is_empty()).collect(),
+            card_header: pattern("card_header", &self.card_header)?,
+            transaction,
+            amount: self.amount.clone(),
+            date_format: DateFormat(self.date_format.clone()),
+            ignore: self.ignore.iter()
+                .map(|i| pattern("ignore", i))
+                .collect::<Result<Vec<Regex>, String>>()?,
+            statement_year: (!self.statement_year.is_empty())
+                .then(|| pattern("statement_year", &self.statement_year))
+                .transpose()?,
+            //AMAZON.COM SEATTLE US USD 12.34
+            foreign: Regex::new(&format!(r"^(.*?)\s+({})\s*([0-9.,' ]*{}\d{{2}})$",
+                CURRENCY_CODES.join("|"), regex::escape(&self.amount.decimal_separator.to_string()))).unwrap(),
+        })
+    }
+}
+
+struct DateFormat(String);
+
+impl DateFormat {
+    //longest first so MMM is not read as MM
+    const TOKENS: [&'static str; 5] = ["YYYY", "MMM", "YY", "MM", "DD"];
+
+    /// Statement dates are kept as "05 JUN" whatever the bank prints.
+    fn normalise(&self, date: &str) -> Option<String> {
+        let (mut day, mut month) = (None, None);
+        let mut format = self.0.as_str();
+        let mut date = date.trim();
+        while !format.is_empty() {
+            if let Some(token) = Self::TOKENS.iter().find(|t| format.starts_with(**t)) {
+                format = &format[token.len()..];
+                let length = match *token {
+                    "MMM" => date.chars().take_while(|c| c.is_ascii_alphabetic()).count(),
+                    "YYYY" | "YY" => token.len(),
+                    _ => date.chars().take(2).take_while(|c| c.is_ascii_digit()).count(),
+                };
+                if length == 0 || length > date.len() || !date.is_char_boundary(length) {
+                    return None;
+                }
+                let (value, rest) = date.split_at(length);
+                date = rest;
+                match *token {
+                    "DD" => day = value.parse::<u32>().ok(),
+                    "MM" => month = value.parse::<usize>().ok(),
+                    "MMM" => month = MONTHS.iter().position(|m| value.to_uppercase().starts_with(m)).map(|m| m + 1),
+                    _ => {}
+                }
+            } else {
+                let c = format.chars().next()?;
+                format = &format[c.len_utf8()..];
+                //separators only have to be there, any spacing is fine
+                date = date.trim_start();
+                if !c.is_whitespace() {
+                    date = date.strip_prefix(c)?.trim_start();
+                }
+            }
+        }
+        let month = MONTHS.get(month?.checked_sub(1)?)?;
+        Some(format!("{:02} {}", day.filter(|d| (1..=31).contains(d))?, month))
+    }
+}
+
+/// A [`StatementProfile`] with its patterns compiled.
+pub(crate) struct Profile {
+    pub name: String,
+    fingerprints: Vec<String>,
+    card_header: Regex,
+    transaction: Regex,
+    amount: AmountFormat,
+    date_format: DateFormat,
+    ignore: Vec<Regex>,
+    statement_year: Option<Regex>,
+    foreign: Regex,
+}
+
+impl Profile {
+    /// Whether the first page of a statement has one of the fingerprints.
+    pub fn detects(&self, lines: &[String]) -> bool {
+        self.fingerprints.is_empty() || lines.iter()
+            .map(|l| l.to_lowercase())
+            .any(|l| self.fingerprints.iter().any(|f| l.contains(f)))
+    }
+
+    /// Year of the statement printed on one of the first page `lines`.
+    pub fn year(&self, lines: &[String]) -> Option<i32> {
+        let pattern = self.statement_year.as_ref()?;
+        lines.iter().find_map(|line| {
+            let captures = pattern.captures(line)?;
+            captures.name("year").or_else(|| captures.get(1))?.as_str().parse::<i32>().ok()
+        })
+    }
+
+    /// Pattern `line` matches, card headers win over transactions the same way as when reading.
+    pub fn line_kind(&self, line: &str) -> LineKind {
+        if self.ignore.iter().any(|i| i.is_match(line)) {
+            LineKind::Ignored
+        } else if self.card_header.is_match(line) {
+            LineKind::Card
+        } else if self.transaction(line).is_some() {
+            LineKind::Transaction
+        } else {
+            LineKind::Other
+        }
+    }
+
+    /// Card number of a card section header.
+    pub fn card(&self, line: &str) -> Option<CardNumber> {
+        CardNumber::parse(&line[self.card_span(line)?])
+    }
+
+    /// Where the card number is in a card section header.
+    pub fn card_span(&self, line: &str) -> Option<Range<usize>> {
+        let captures = self.card_header.captures(line)?;
+        captures.name("card").or_else(|| captures.get(1))
+            .map(|c| c.range())
+    }
+
+    /// Where the description and the amount are in a transaction line.
+    #[cfg(any(feature = "gui", not(target_arch = "wasm32")))]
+    pub fn transaction_spans(&self, line: &str) -> Option<(Range<usize>, Range<usize>)> {
+        self.transaction(line)?;
+        let captures = self.transaction.captures(line)?;
+        Some((captures.name("description")?.range(), captures.name("amount")?.range()))
+    }
+
+    /// Date, description and amount of a transaction line.
+    pub fn transaction(&self, line: &str) -> Option<(String, String, f64)> {
+        let captures = self.transaction.captures(line)?;
+        let group = |name: &str| captures.name(name).map(|c| c.as_str()).unwrap_or_default();
+        Some((
+            self.date_format.normalise(group("date"))?,
+            group("description").trim().to_string(),
+            self.amount.parse(group("amount")).ok()?,
+        ))
+    }
+
+    /// Why the amount of a line shaped like a transaction could not be read.
+    pub fn amount_error(&self, line: &str) -> Option<String> {
+        let captures = self.transaction.captures(line)?;
+        self.amount.parse(captures.name("amount")?.as_str()).err()
+    }
+
+    /// Length of the description before the foreign amount at its end.
+    #[cfg(any(feature = "gui", not(target_arch = "wasm32")))]
+    pub fn foreign_amount_start(&self, description: &str) -> Option<usize> {
+        self.foreign.captures(description)?.get(1).map(|c| c.end())
+    }
+
+    /// Description without the foreign amount at its end, and that amount.
+    pub fn foreign_amount(&self, description: &str) -> Option<(String, String, f64)> {
+        let captures = self.foreign.captures(description)?;
+        //the foreign amount is unsigned, the sign comes from the statement amount
+        let amount = AmountFormat { charges_negative: false, ..self.amount.clone() }.parse(&captures[3]).ok()?;
+        Some((captures[1].to_string(), captures[2].to_string(), amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn citibank() -> Profile {
+        StatementProfile::citibank().compile().unwrap()
+    }
+
+    #[test]
+    fn citibank_card_headers() {
+        let profile = citibank();
+        let id = |line: &str| profile.card(line).map(|c| c.id);
+        assert_eq!(id("CITI REWARDS CARD 1234 5678 9012 5136 - J DOE"), Some("1234567890125136".to_string()));
+        assert_eq!(id("CITI REWARDS CARD 1234-5678-9012-5136 - J DOE"), Some("1234567890125136".to_string()));
+        assert_eq!(id("CITI PRESTIGE CARD 3782-822463-10005 - J DOE"), Some("378282246310005".to_string()));
+        assert_eq!(id("CITI REWARDS CARD XXXX XXXX XXXX 5136 - J DOE"), Some("XXXXXXXXXXXX5136".to_string()));
+    }
+
+    #[test]
+    fn citibank_credits() {
+        let profile = citibank();
+        for line in ["05 JUN REFUND 12.34 CR", "05 JUN REFUND (12.34)", "05 JUN REFUND -12.34", "05 JUN REFUND 12.34-"] {
+            assert_eq!(profile.transaction(line), Some(("05 JUN".to_string(), "REFUND".to_string(), -12.34)), "{}", line);
+        }
+        assert_eq!(profile.transaction("05 JUN GRAB 12.34 DR"), Some(("05 JUN".to_string(), "GRAB".to_string(), 12.34)));
+    }
+
+    #[test]
+    fn citibank_foreign_credit() {
+        let line = "07 JUN AMAZON.COM SEATTLE US USD 10.00 13.60 CR";
+        assert_eq!(citibank().transaction(line).map(|t| t.2), Some(-13.6));
+    }
+}
@@ -0,0 +1,310 @@
+use std::cmp::Ordering;
+use crate::bill_reader::Transaction;
+
+/// Year, month and day used to compare transaction dates.
+type Date = (i32, u32, u32);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Term {
+    Text(String),
+    Amount(Ordering, bool, f64),
+    Tag(String),
+    Card(String),
+    Description(String),
+    Reviewed,
+    Before(Date),
+    After(Date),
+}
+
+/// Parsed search box text such as `amount>50 tag:joint card:5136 desc:grab before:2024-06-10`.
+/// Terms are combined with "and", a leading `-` negates a term, `tag:none` matches untagged
+/// transactions and `is:reviewed` the ones marked as reviewed.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    terms: Vec<(bool, Term)>,
+}
+
+//"word", "key:value" or "key:\"two words\""
+fn tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
+
+fn parse_date(text: &str) -> Result<Date, String> {
+    let parts = text.split('-').collect::<Vec<&str>>();
+    let [year, month, day] = parts[..] else {
+        return Err(format!("\"{}\" is not a YYYY-MM-DD date", text));
+    };
+    match (year.parse::<i32>(), month.parse::<u32>(), day.parse::<u32>()) {
+        (Ok(year), Ok(month @ 1..=12), Ok(day @ 1..=31)) => Ok((year, month, day)),
+        _ => Err(format!("\"{}\" is not a YYYY-MM-DD date", text)),
+    }
+}
+
+fn parse_amount(text: &str) -> Result<Term, String> {
+    let (ordering, or_equal, value) = if let Some(value) = text.strip_prefix(">=") {
+        (Ordering::Greater, true, value)
+    } else if let Some(value) = text.strip_prefix("<=") {
+        (Ordering::Less, true, value)
+    } else if let Some(value) = text.strip_prefix('>') {
+        (Ordering::Greater, false, value)
+    } else if let Some(value) = text.strip_prefix('<') {
+        (Ordering::Less, false, value)
+    } else if let Some(value) = text.strip_prefix('=').or_else(|| text.strip_prefix(':')) {
+        (Ordering::Equal, true, value)
+    } else {
+        return Err(format!("Use amount>, amount<, amount>=, amount<= or amount= instead of \"amount{}\"", text));
+    };
+    value.replace(',', "").parse::<f64>()
+        .map(|value| Term::Amount(ordering, or_equal, value))
+        .map_err(|_| format!("\"{}\" is not an amount", value))
+}
+
+fn parse_term(token: &str) -> Result<Term, String> {
+    if let Some(rest) = token.strip_prefix("amount") {
+        if rest.starts_with(['>', '<', '=', ':']) {
+            return parse_amount(rest);
+        }
+    }
+    let Some((key, value)) = token.split_once(':') else {
+        return Ok(Term::Text(token.to_lowercase()));
+    };
+    if value.is_empty() {
+        return Err(format!("\"{}\" needs a value", key));
+    }
+    match key.to_lowercase().as_str() {
+        "tag" => Ok(Term::Tag(value.to_lowercase())),
+        "card" => Ok(Term::Card(value.replace(' ', ""))),
+        "desc" | "description" => Ok(Term::Description(value.to_lowercase())),
+        "is" if value.eq_ignore_ascii_case("reviewed") => Ok(Term::Reviewed),
+        "is" => Err(format!("Unknown \"is:{}\", use is:reviewed", value)),
+        "before" => parse_date(value).map(Term::Before),
+        "after" => parse_date(value).map(Term::After),
+        _ => Err(format!("Unknown filter \"{}:\", use amount, tag, card, desc, is, before or after", key)),
+    }
+}
+
+/// Full date of a transaction on the statement of `statement_month` ("2024-06"). Earlier
+/// months after the statement month belong to the year before, as in a January statement.
+pub fn transaction_date(transaction: &Transaction, statement_month: Option<&str>) -> Option<Date> {
+    let (day, month) = transaction.day_month()?;
+    let (year, last_month) = statement_month?.split_once('-')?;
+    let (year, last_month) = (year.parse::<i32>().ok()?, last_month.parse::<u32>().ok()?);
+    Some((if month > last_month { year - 1 } else { year }, month, day))
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let terms = tokens(text).iter()
+            .map(|token| match token.strip_prefix('-') {
+                Some(token) if !token.is_empty() => parse_term(token).map(|term| (true, term)),
+                _ => parse_term(token).map(|term| (false, term)),
+            })
+            .collect::<Result<Vec<(bool, Term)>, String>>()?;
+        Ok(Self { terms })
+    }
+
+    pub fn matches(&self, transaction: &Transaction, statement_month: Option<&str>) -> bool {
+        self.matches_amount(transaction, transaction.amount, statement_month)
+    }
+
+    /// Like [`Query::matches`], with the amount filters checking `amount` rather than the statement
+    /// amount, so the table filters on the home currency amount it shows.
+    pub fn matches_amount(&self, transaction: &Transaction, amount: f64, statement_month: Option<&str>) -> bool {
+        self.terms.iter().all(|(negated, term)| Self::term_matches(term, transaction, amount, statement_month) != *negated)
+    }
+
+    fn term_matches(term: &Term, transaction: &Transaction, amount: f64, statement_month: Option<&str>) -> bool {
+        let contains = |text: &str, value: &str| text.to_lowercase().contains(value);
+        match term {
+            Term::Text(text) => contains(&transaction.description, text)
+                || contains(&transaction.merchant.name, text)
+                || contains(&transaction.notes, text),
+            Term::Amount(ordering, or_equal, value) => {
+                let cents = (amount * 100.0).round() as i64;
+                let value = (value * 100.0).round() as i64;
+                let actual = cents.cmp(&value);
+                actual == *ordering || (*or_equal && actual == Ordering::Equal)
+            }
+            Term::Tag(tag) if tag == "none" => transaction.tags.is_empty(),
+            Term::Tag(tag) => transaction.tags.iter().any(|t| t.to_lowercase() == *tag),
+            Term::Card(card) => transaction.card_ends_with(card),
+            Term::Description(text) => contains(&transaction.description, text) || contains(&transaction.merchant.name, text),
+            Term::Reviewed => transaction.reviewed,
+            Term::Before(date) => transaction_date(transaction, statement_month).is_some_and(|d| d < *date),
+            Term::After(date) => transaction_date(transaction, statement_month).is_some_and(|d| d > *date),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum SortColumn {
+    Date,
+    Description,
+    Amount,
+    Card,
+}
+
+#[cfg(feature = "gui")]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Sort {
+    pub column: SortColumn,
+    pub ascending: bool,
+}
+
+#[cfg(feature = "gui")]
+//without a statement month the year is unknown, day and month still sort within one statement
+fn sort_date(transaction: &Transaction, statement_month: Option<&str>) -> Option<Date> {
+    transaction_date(transaction, statement_month)
+        .or_else(|| transaction.day_month().map(|(day, month)| (0, month, day)))
+}
+
+#[cfg(feature = "gui")]
+impl Sort {
+    /// Sorts row indices into `transactions`, ties keep the statement order.
+    pub fn apply(&self, rows: &mut [usize], transactions: &[Transaction], statement_month: Option<&str>) {
+        rows.sort_by(|a, b| {
+            let (a, b) = (&transactions[*a], &transactions[*b]);
+            let ordering = match self.column {
+                SortColumn::Date => sort_date(a, statement_month).cmp(&sort_date(b, statement_month)),
+                SortColumn::Description => a.description.to_lowercase().cmp(&b.description.to_lowercase()),
+                SortColumn::Amount => a.amount.total_cmp(&b.amount),
+                SortColumn::Card => a.card.cmp(&b.card),
+            };
+            if self.ascending { ordering } else { ordering.reverse() }
+        });
+    }
+}
+
+#[cfg(feature = "gui")]
+/// Search and sort kept under a name so it can be brought back later.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct SavedView {
+    pub name: String,
+    pub search: String,
+    pub sort: Option<Sort>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bill_reader::JOINT_TAG;
+
+    fn transaction(date: &str, description: &str, amount: f64, tags: &[&str]) -> Transaction {
+        let tags = tags.iter().map(|t| t.to_string()).collect();
+        Transaction::new(date.to_string(), description.to_string(), amount, "1234 5678 9012 5136".to_string(), tags)
+    }
+
+    fn matches(query: &str, transaction: &Transaction) -> bool {
+        Query::parse(query).unwrap().matches(transaction, Some("2025-01"))
+    }
+
+    #[test]
+    fn amounts() {
+        let grab = transaction("05 JAN", "GRAB", 50.0, &[]);
+        assert!(matches("amount>49.99", &grab));
+        assert!(!matches("amount>50", &grab));
+        assert!(matches("amount>=50", &grab));
+        assert!(matches("amount<50.01", &grab));
+        assert!(!matches("amount<50", &grab));
+        assert!(matches("amount<=50", &grab));
+        assert!(matches("amount=50.00", &grab));
+        assert!(matches("amount:1,000", &transaction("05 JAN", "IKEA", 1000.0, &[])));
+    }
+
+    #[test]
+    fn amounts_shown_in_another_currency() {
+        let query = Query::parse("amount>50").unwrap();
+        let grab = transaction("05 JAN", "GRAB", 40.0, &[]);
+        assert!(!query.matches(&grab, None));
+        assert!(query.matches_amount(&grab, 54.0, None));
+    }
+
+    #[test]
+    fn text_tags_cards_and_descriptions() {
+        let mut grab = transaction("05 JAN", "GRAB* A-2K9XYZ", 12.5, &[JOINT_TAG]);
+        grab.merchant.name = "Grab".to_string();
+        grab.notes = "Airport taxi".to_string();
+        assert!(matches("grab", &grab));
+        assert!(matches("AIRPORT", &grab));
+        assert!(matches("tag:joint", &grab));
+        assert!(!matches("tag:personal", &grab));
+        assert!(matches("card:5136", &grab));
+        assert!(matches("card:\"9012 5136\"", &grab));
+        assert!(!matches("card:0005", &grab));
+        assert!(matches("desc:a-2k9", &grab));
+        assert!(!matches("desc:airport", &grab));
+        assert!(!matches("is:reviewed", &grab));
+        grab.reviewed = true;
+        assert!(matches("is:reviewed", &grab));
+    }
+
+    #[test]
+    fn untagged_transactions() {
+        assert!(matches("tag:none", &transaction("05 JAN", "GRAB", 1.0, &[])));
+        assert!(!matches("tag:none", &transaction("05 JAN", "GRAB", 1.0, &[JOINT_TAG])));
+    }
+
+    #[test]
+    fn quoted_values() {
+        let storage = transaction("05 JAN", "COLD STORAGE", 1.0, &["Home Office"]);
+        assert!(matches("\"cold storage\"", &storage));
+        assert!(!matches("\"storage cold\"", &storage));
+        assert!(matches("tag:\"home office\"", &storage));
+        assert!(matches("desc:\"cold storage\" tag:\"Home Office\"", &storage));
+    }
+
+    #[test]
+    fn negation() {
+        let grab = transaction("05 JAN", "GRAB", 12.5, &[JOINT_TAG]);
+        assert!(!matches("-grab", &grab));
+        assert!(matches("-tag:none", &grab));
+        assert!(matches("-amount>20 grab", &grab));
+        assert!(!matches("-amount<20 grab", &grab));
+        //a lone "-" is plain text
+        assert!(!matches("-", &grab));
+    }
+
+    #[test]
+    fn dates_across_a_year_change() {
+        //on the January 2025 statement "28 DEC" is in 2024
+        let december = transaction("28 DEC", "GRAB", 1.0, &[]);
+        let january = transaction("03 JAN", "GRAB", 1.0, &[]);
+        assert!(matches("before:2025-01-01", &december));
+        assert!(!matches("before:2025-01-01", &january));
+        assert!(matches("after:2024-12-31", &january));
+        assert!(!matches("after:2024-12-31", &december));
+        assert!(matches("after:2024-12-27 before:2024-12-29", &december));
+        //without a statement month dates are unknown and match neither
+        assert!(!Query::parse("before:2030-01-01").unwrap().matches(&december, None));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |query: &str| Query::parse(query).unwrap_err();
+        assert_eq!(error("amount:"), "\"\" is not an amount");
+        assert_eq!(error("amount>fifty"), "\"fifty\" is not an amount");
+        assert_eq!(error("tag:"), "\"tag\" needs a value");
+        assert_eq!(error("is:done"), "Unknown \"is:done\", use is:reviewed");
+        assert_eq!(error("before:2024-13-01"), "\"2024-13-01\" is not a YYYY-MM-DD date");
+        assert_eq!(error("after:yesterday"), "\"yesterday\" is not a YYYY-MM-DD date");
+        assert_eq!(error("merchant:grab"), "Unknown filter \"merchant:\", use amount, tag, card, desc, is, before or after");
+    }
+}
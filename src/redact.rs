@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use crate::bill_reader::{LineKind, PAGE_BREAK};
+use crate::merchant::MerchantNormaliser;
+use crate::profile::Profile;
+
+/// Replaces the private parts of statement text, same inputs give the same output so a
+/// merchant or card keeps its fake name across pages.
+struct Anonymiser<'a> {
+    profile: &'a Profile,
+    normaliser: MerchantNormaliser,
+    state: u64,
+    cards: Vec<(String, String)>,
+    merchants: HashMap<String, String>,
+}
+
+impl Anonymiser<'_> {
+    //xorshift, good enough to scramble digits and small enough to need no crate
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Random digits in place of the digits of `text`, the first digit of a number stays non zero
+    /// so "1,234.50" keeps its width.
+    fn scramble_digits(&mut self, text: &str) -> String {
+        let mut previous = None;
+        text.chars()
+            .map(|c| {
+                let leading = !previous.is_some_and(|p: char| p.is_ascii_digit() || p == ',' || p == '.');
+                previous = Some(c);
+                match c {
+                    '0'..='9' if leading && c != '0' => char::from(b'1' + (self.next() % 9) as u8),
+                    '0'..='9' => char::from(b'0' + (self.next() % 10) as u8),
+                    c => c,
+                }
+            })
+            .collect()
+    }
+
+    /// "4000 0000 0000 0001" style number with the separators of `card`.
+    fn fake_card(&mut self, card: &str) -> String {
+        //a profile whose card group matches nothing has no number to hide
+        if card.is_empty() {
+            return String::new();
+        }
+        if let Some((_, fake)) = self.cards.iter().find(|(c, _)| c == card) {
+            return fake.clone();
+        }
+        let digits = card.chars().filter(char::is_ascii_digit).count();
+        let number = format!("4{:0>width$}", self.cards.len() + 1, width = digits.saturating_sub(1));
+        let mut number = number.chars();
+        let fake = card.chars()
+            .map(|c| if c.is_ascii_digit() { number.next().unwrap_or('0') } else { c })
+            .collect::<String>();
+        self.cards.push((card.to_string(), fake.clone()));
+        fake
+    }
+
+    /// "MERCHANT 001" in place of the merchant, keeping the city, country and foreign amount.
+    fn fake_description(&mut self, description: &str) -> String {
+        let (rest, foreign) = match self.profile.foreign_amount_start(description) {
+            Some(end) => description.split_at(end),
+            None => (description, ""),
+        };
+        let merchant = self.normaliser.split_location(rest).0;
+        let location = &rest[merchant.len()..];
+        let count = self.merchants.len();
+        let fake = self.merchants.entry(merchant.to_uppercase())
+            .or_insert_with(|| format!("MERCHANT {:03}", count + 1))
+            .clone();
+        format!("{}{}{}", fake, location, self.scramble_digits(foreign))
+    }
+
+    /// Text outside the known card numbers gets its digits scrambled.
+    fn scramble_line(&mut self, line: &str) -> String {
+        let found = self.cards.iter()
+            .filter(|(card, _)| !card.is_empty())
+            .filter_map(|(card, fake)| line.find(card.as_str()).map(|start| (start, card.len(), fake.clone())))
+            .min_by_key(|(start, _, _)| *start);
+        match found {
+            Some((start, length, fake)) => format!("{}{}{}",
+                self.scramble_digits(&line[..start]), fake, self.scramble_line(&line[start + length..])),
+            None => self.scramble_digits(line),
+        }
+    }
+
+    fn line(&mut self, line: &str) -> String {
+        match self.profile.line_kind(line) {
+            LineKind::Card => match self.profile.card_span(line) {
+                //the card holder's name usually follows the number
+                Some(span) => {
+                    let fake = self.fake_card(&line[span.clone()]);
+                    let rest = line[span.end..].chars()
+                        .map(|c| if c.is_alphabetic() { 'X' } else { c })
+                        .collect::<String>();
+                    format!("{}{}{}", &line[..span.start], fake, self.scramble_digits(&rest))
+                }
+                None => self.scramble_line(line),
+            },
+            LineKind::Transaction => match self.profile.transaction_spans(line) {
+                Some((description, amount)) if description.end <= amount.start => format!("{}{}{}{}{}",
+                    &line[..description.start],
+                    self.fake_description(&line[description.clone()]),
+                    self.scramble_line(&line[description.end..amount.start]),
+                    self.scramble_digits(&line[amount.clone()]),
+                    self.scramble_line(&line[amount.end..])),
+                _ => self.scramble_line(line),
+            },
+            LineKind::Ignored | LineKind::Other => self.scramble_line(line),
+        }
+    }
+}
+
+/// Statement text with fake card numbers and merchants and scrambled amounts, laid out like the
+/// original so it reproduces parsing problems without the private details. Words on lines which
+/// match no pattern, such as the card holder's address, are kept and need checking by hand.
+pub fn anonymise(profile: &Profile, pages: &[Vec<String>]) -> String {
+    let mut hasher = DefaultHasher::new();
+    pages.hash(&mut hasher);
+    let mut anonymiser = Anonymiser {
+        profile,
+        normaliser: MerchantNormaliser::default(),
+        state: hasher.finish() | 1,
+        cards: Vec::new(),
+        merchants: HashMap::new(),
+    };
+    //every card gets its fake number before the lines mentioning it elsewhere are scrambled
+    for line in pages.iter().flatten() {
+        if let Some(span) = profile.card_span(line) {
+            anonymiser.fake_card(&line[span]);
+        }
+    }
+    pages.iter()
+        .map(|page| page.iter()
+            .map(|line| anonymiser.line(line))
+            .collect::<Vec<String>>()
+            .join("\n"))
+        .collect::<Vec<String>>()
+        .join(&format!("\n{}\n", PAGE_BREAK))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::StatementProfile;
+
+    #[test]
+    fn empty_card_numbers_are_not_replaced() {
+        let profile = StatementProfile {
+            card_header: r"CARD (?P<card>\d*)".to_string(),
+            ..StatementProfile::citibank()
+        }.compile().unwrap();
+        let pages = vec![vec!["CARD HOLDER".to_string(), "Total 123.45".to_string()]];
+        let sample = anonymise(&profile, &pages);
+        assert!(sample.starts_with("CARD XXXXXX\nTotal "));
+        assert!(!sample.contains("123.45"));
+    }
+}
@@ -0,0 +1,76 @@
+use crate::bill_reader::{Transaction, JOINT_TAG};
+use crate::summary::{period, SplitSettings, Summary};
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 2em auto; max-width: 50em; color: #222; }
+h1 { margin-bottom: 0; }
+.period { color: #666; margin-top: 0.2em; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1.5em; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.3em 0.5em; text-align: left; vertical-align: top; }
+td.amount, th.amount { text-align: right; white-space: nowrap; }
+tr.total td { font-weight: bold; border-top: 2px solid #222; }
+.notes { color: #666; font-size: 0.9em; }
+@media print { body { margin: 0; max-width: none; } h2 { break-after: avoid; } tr { break-inside: avoid; } }
+"#;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn totals_table(html: &mut String, title: &str, totals: &[(String, f64)]) {
+    html.push_str(&format!("<h2>Per {}</h2>\n<table>\n<tr><th>{}</th><th class=\"amount\">Total</th></tr>\n", title.to_lowercase(), title));
+    for (name, total) in totals {
+        html.push_str(&format!("<tr><td>{}</td><td class=\"amount\">{:.2}</td></tr>\n", escape(name), total));
+    }
+    html.push_str("</table>\n");
+}
+
+/// Self-contained HTML page with the split of a statement, meant to be printed or sent as is.
+pub fn to_html(transactions: &[Transaction], settings: &SplitSettings) -> String {
+    let summary = Summary::new(transactions, settings);
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Bill split report</title>\n");
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+    html.push_str("<h1>Bill split report</h1>\n");
+    html.push_str(&format!("<p class=\"period\">{} &middot; {} transactions &middot; total {:.2}</p>\n", escape(&period(transactions)), summary.count, summary.total));
+
+    html.push_str("<h2>Settlement</h2>\n");
+    let settlements = summary.settlements(settings);
+    if settlements.is_empty() {
+        html.push_str("<p>Nothing to settle.</p>\n");
+    } else {
+        html.push_str("<table>\n<tr><th>From</th><th>To</th><th class=\"amount\">Amount</th></tr>\n");
+        for settlement in &settlements {
+            html.push_str(&format!("<tr><td>{}</td><td>{}</td><td class=\"amount\">{:.2}</td></tr>\n",
+                escape(&settlement.from), escape(&settlement.to), settlement.amount));
+        }
+        html.push_str("</table>\n");
+    }
+
+    totals_table(&mut html, "Person", &summary.per_person.into_iter().collect::<Vec<(String, f64)>>());
+    totals_table(&mut html, "Card", &summary.per_card.into_iter().collect::<Vec<(String, f64)>>());
+    totals_table(&mut html, "Tag", &summary.per_tag.into_iter().collect::<Vec<(String, f64)>>());
+
+    html.push_str("<h2>Joint transactions</h2>\n<table>\n<tr><th>Date</th><th>Description</th><th class=\"amount\">Amount</th></tr>\n");
+    let mut joint_total = 0f64;
+    for transaction in transactions.iter().filter(|t| t.tags.iter().any(|tag| tag == JOINT_TAG)) {
+        joint_total += transaction.amount;
+        let description = if transaction.merchant.name.is_empty() {
+            transaction.description.clone()
+        } else {
+            transaction.merchant.display()
+        };
+        html.push_str(&format!("<tr><td>{}</td><td>{}", escape(&transaction.date), escape(&description)));
+        if !transaction.notes.is_empty() {
+            html.push_str(&format!("<div class=\"notes\">{}</div>", escape(&transaction.notes).replace('\n', "<br>")));
+        }
+        html.push_str(&format!("</td><td class=\"amount\">{:.2}</td></tr>\n", transaction.amount));
+    }
+    html.push_str(&format!("<tr class=\"total\"><td></td><td>Total</td><td class=\"amount\">{:.2}</td></tr>\n</table>\n", joint_total));
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
@@ -0,0 +1,27 @@
+use crate::bill_reader::Transaction;
+use crate::query::Query;
+
+/// Tags the transactions matching `condition`, which uses the search box syntax.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct TagRule {
+    pub condition: String,
+    pub tag: String,
+}
+
+/// Gives each transaction the tag of the first rule it matches, transactions matching no rule
+/// keep their tags. Returns how many transactions were tagged.
+pub fn apply(rules: &[TagRule], transactions: &mut [Transaction], statement_month: Option<&str>) -> Result<usize, String> {
+    let rules = rules.iter()
+        .map(|r| Query::parse(&r.condition)
+            .map(|q| (q, r))
+            .map_err(|e| format!("Rule \"{}\": {}", r.condition, e)))
+        .collect::<Result<Vec<(Query, &TagRule)>, String>>()?;
+    let mut tagged = 0;
+    for transaction in transactions {
+        if let Some((_, rule)) = rules.iter().find(|(q, _)| q.matches(transaction, statement_month)) {
+            transaction.tags = vec![rule.tag.clone()];
+            tagged += 1;
+        }
+    }
+    Ok(tagged)
+}
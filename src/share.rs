@@ -0,0 +1,168 @@
+#[cfg(feature = "gui")]
+use anyhow::Result;
+#[cfg(feature = "gui")]
+use base64::Engine;
+#[cfg(feature = "gui")]
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use crate::bill_reader::{Transaction, JOINT_TAG};
+use crate::summary::{period, SplitSettings, Summary};
+
+#[cfg(feature = "gui")]
+pub const SNAPSHOT_FRAGMENT: &str = "snapshot=";
+
+const TOP_JOINT_ITEMS: usize = 5;
+
+//inflating a hostile link should not eat all the memory of the tab
+#[cfg(all(feature = "gui", any(target_arch = "wasm32", test)))]
+const SNAPSHOT_LIMIT: usize = 4 * 1024 * 1024;
+
+#[cfg(feature = "gui")]
+/// Read-only copy of a statement which travels in the URL fragment, so it never reaches a server.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Snapshot {
+    pub split_settings: SplitSettings,
+    pub transactions: Vec<Transaction>,
+}
+
+#[cfg(feature = "gui")]
+impl Snapshot {
+    /// Receipts and statement PDFs stay in the local attachment store, only their names would
+    /// survive the trip. Links end up in browser history and chat previews, so cards keep their
+    /// last digits only and notes are left out unless `notes` is set.
+    pub fn new(transactions: &[Transaction], split_settings: &SplitSettings, notes: bool) -> Self {
+        Self {
+            split_settings: split_settings.clone(),
+            transactions: transactions.iter()
+                .map(|t| {
+                    let last_digits = last_digits(t);
+                    Transaction {
+                        card: format!("\u{2022}\u{2022}\u{2022}\u{2022} {}", last_digits),
+                        card_id: last_digits,
+                        notes: if notes { t.notes.clone() } else { String::new() },
+                        attachments: Vec::new(),
+                        source: None,
+                        ..t.clone()
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    pub fn encode(&self) -> Result<String> {
+        let text = ron::to_string(self)?;
+        let compressed = miniz_oxide::deflate::compress_to_vec(text.as_bytes(), 9);
+        Ok(URL_SAFE_NO_PAD.encode(compressed))
+    }
+
+    //only the web app opens snapshot links
+    #[cfg(any(target_arch = "wasm32", test))]
+    pub fn decode(encoded: &str) -> Result<Self> {
+        let compressed = URL_SAFE_NO_PAD.decode(encoded.trim())?;
+        let text = miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, SNAPSHOT_LIMIT)
+            .map_err(|e| anyhow::anyhow!("Cannot read the snapshot: {}", e))?;
+        Ok(ron::from_str(std::str::from_utf8(&text)?)?)
+    }
+
+    /// `base_url` with the snapshot appended as the fragment, any existing fragment is replaced.
+    pub fn url(&self, base_url: &str) -> Result<String> {
+        let base_url = base_url.split('#').next().unwrap_or_default();
+        Ok(format!("{}#{}{}", base_url, SNAPSHOT_FRAGMENT, self.encode()?))
+    }
+}
+
+#[cfg(feature = "gui")]
+fn last_digits(transaction: &Transaction) -> String {
+    let id = transaction.card_key();
+    id.chars().skip(id.chars().count().saturating_sub(4)).collect()
+}
+
+/// Plain text summary meant to be pasted into a chat.
+pub fn summary_text(transactions: &[Transaction], settings: &SplitSettings) -> String {
+    let summary = Summary::new(transactions, settings);
+    let mut lines = vec![
+        format!("Bill split {}", period(transactions)),
+        format!("Total: {:.2} ({} transactions)", summary.total, summary.count),
+    ];
+
+    if !summary.per_tag.is_empty() {
+        lines.push(String::new());
+        lines.push("Per tag".to_string());
+        for (tag, total) in &summary.per_tag {
+            lines.push(format!("  {}: {:.2}", tag, total));
+        }
+    }
+
+    let settlements = summary.settlements(settings);
+    if !settlements.is_empty() {
+        lines.push(String::new());
+        lines.push("Settlement".to_string());
+        for settlement in settlements {
+            lines.push(format!("  {} → {}: {:.2}", settlement.from, settlement.to, settlement.amount));
+        }
+    }
+
+    let mut joint = transactions.iter()
+        .filter(|t| t.tags.iter().any(|tag| tag == JOINT_TAG))
+        .collect::<Vec<&Transaction>>();
+    if !joint.is_empty() {
+        joint.sort_by(|a, b| b.amount.total_cmp(&a.amount));
+        lines.push(String::new());
+        lines.push("Top joint items".to_string());
+        for transaction in joint.into_iter().take(TOP_JOINT_ITEMS) {
+            let name = if transaction.merchant.name.is_empty() { &transaction.description } else { &transaction.merchant.name };
+            lines.push(format!("  {} {}: {:.2}", transaction.date, name, transaction.amount));
+        }
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(all(test, feature = "gui"))]
+mod tests {
+    use super::*;
+    use crate::attachment::Attachment;
+    use crate::bill_reader::Source;
+
+    fn transaction() -> Transaction {
+        let mut transaction = Transaction::new("05 JUN".to_string(), "GRAB".to_string(), 12.5,
+            "1234 5678 9012 5136".to_string(), vec![JOINT_TAG.to_string()]);
+        transaction.card_id = "1234567890125136".to_string();
+        transaction.notes = "Dinner with Sam".to_string();
+        transaction.attachments.push(Attachment::new("receipt.jpg".to_string(), b"receipt"));
+        transaction.source = Some(Source { statement: None, page: 0, bounds: None });
+        transaction
+    }
+
+    #[test]
+    fn snapshots_round_trip() {
+        let snapshot = Snapshot::new(&[transaction()], &SplitSettings::default(), true);
+        let decoded = Snapshot::decode(&snapshot.encode().unwrap()).unwrap();
+        assert_eq!(serde_json::to_value(&decoded.transactions).unwrap(), serde_json::to_value(&snapshot.transactions).unwrap());
+        assert_eq!(decoded.split_settings.people, snapshot.split_settings.people);
+    }
+
+    #[test]
+    fn card_numbers_are_masked() {
+        let snapshot = Snapshot::new(&[transaction()], &SplitSettings::default(), true);
+        let transaction = &snapshot.transactions[0];
+        assert_eq!(transaction.card, "\u{2022}\u{2022}\u{2022}\u{2022} 5136");
+        assert_eq!(transaction.card_id, "5136");
+        assert!(transaction.attachments.is_empty());
+        assert!(transaction.source.is_none());
+        let text = ron::to_string(&snapshot).unwrap();
+        assert!(!text.contains("1234"), "{}", text);
+    }
+
+    #[test]
+    fn notes_are_left_out_unless_opted_in() {
+        assert_eq!(Snapshot::new(&[transaction()], &SplitSettings::default(), false).transactions[0].notes, "");
+        assert_eq!(Snapshot::new(&[transaction()], &SplitSettings::default(), true).transactions[0].notes, "Dinner with Sam");
+    }
+
+    #[test]
+    fn urls_replace_the_fragment() {
+        let snapshot = Snapshot::new(&[transaction()], &SplitSettings::default(), false);
+        let url = snapshot.url("https://example.com/billsplit/#old").unwrap();
+        assert_eq!(url, format!("https://example.com/billsplit/#{}{}", SNAPSHOT_FRAGMENT, snapshot.encode().unwrap()));
+    }
+}
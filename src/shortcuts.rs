@@ -0,0 +1,139 @@
+use egui::{Key, KeyboardShortcut, Modifiers};
+use crate::bill_reader::{JOINT_TAG, PERSONAL_TAG};
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    NextRow,
+    PreviousRow,
+    ToggleReviewed,
+    OpenDetails,
+    Search,
+    CommandPalette,
+    Help,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::NextRow,
+        Action::PreviousRow,
+        Action::ToggleReviewed,
+        Action::OpenDetails,
+        Action::Search,
+        Action::CommandPalette,
+        Action::Help,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::NextRow => "Next row",
+            Action::PreviousRow => "Previous row",
+            Action::ToggleReviewed => "Mark as reviewed",
+            Action::OpenDetails => "Show details",
+            Action::Search => "Search",
+            Action::CommandPalette => "Command palette",
+            Action::Help => "Keyboard shortcuts",
+        }
+    }
+
+    fn default_shortcut(&self) -> KeyboardShortcut {
+        match self {
+            Action::NextRow => KeyboardShortcut::new(Modifiers::NONE, Key::ArrowDown),
+            Action::PreviousRow => KeyboardShortcut::new(Modifiers::NONE, Key::ArrowUp),
+            Action::ToggleReviewed => KeyboardShortcut::new(Modifiers::NONE, Key::Space),
+            Action::OpenDetails => KeyboardShortcut::new(Modifiers::NONE, Key::Enter),
+            Action::Search => KeyboardShortcut::new(Modifiers::NONE, Key::Slash),
+            Action::CommandPalette => KeyboardShortcut::new(Modifiers::COMMAND, Key::K),
+            Action::Help => KeyboardShortcut::new(Modifiers::NONE, Key::F1),
+        }
+    }
+
+    /// Actions which also work while a text field has the keyboard.
+    pub fn is_global(&self) -> bool {
+        matches!(self, Action::CommandPalette | Action::Help)
+    }
+}
+
+/// Key toggling `tag` on the focused row.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct TagShortcut {
+    pub tag: String,
+    pub shortcut: KeyboardShortcut,
+}
+
+/// What a binding in the shortcuts window points at, used while waiting for the new key.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Binding {
+    Action(Action),
+    Tag(usize),
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct Shortcuts {
+    pub actions: Vec<(Action, KeyboardShortcut)>,
+    pub tags: Vec<TagShortcut>,
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        Self {
+            actions: Action::ALL.iter().map(|a| (*a, a.default_shortcut())).collect(),
+            tags: vec![
+                TagShortcut { tag: PERSONAL_TAG.to_string(), shortcut: KeyboardShortcut::new(Modifiers::NONE, Key::Num1) },
+                TagShortcut { tag: JOINT_TAG.to_string(), shortcut: KeyboardShortcut::new(Modifiers::NONE, Key::Num2) },
+            ],
+        }
+    }
+}
+
+impl Shortcuts {
+    /// Shortcut of `action`, the default one for actions added after the settings were saved.
+    pub fn get(&self, action: Action) -> KeyboardShortcut {
+        self.actions.iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, s)| *s)
+            .unwrap_or_else(|| action.default_shortcut())
+    }
+
+    pub fn set(&mut self, binding: Binding, shortcut: KeyboardShortcut) {
+        match binding {
+            Binding::Action(action) => match self.actions.iter_mut().find(|(a, _)| *a == action) {
+                Some((_, s)) => *s = shortcut,
+                None => self.actions.push((action, shortcut)),
+            },
+            Binding::Tag(index) => {
+                if let Some(tag) = self.tags.get_mut(index) {
+                    tag.shortcut = shortcut;
+                }
+            }
+        }
+    }
+
+    /// Actions whose shortcut was pressed this frame, the key presses are consumed.
+    pub fn pressed(&self, ctx: &egui::Context, text_focused: bool) -> Vec<Action> {
+        Action::ALL.iter()
+            .filter(|a| !text_focused || a.is_global())
+            .filter(|a| ctx.input_mut(|i| i.consume_shortcut(&self.get(**a))))
+            .copied()
+            .collect()
+    }
+
+    /// Tags whose shortcut was pressed this frame.
+    pub fn pressed_tags(&self, ctx: &egui::Context) -> Vec<String> {
+        self.tags.iter()
+            .filter(|t| !t.tag.is_empty() && ctx.input_mut(|i| i.consume_shortcut(&t.shortcut)))
+            .map(|t| t.tag.clone())
+            .collect()
+    }
+}
+
+/// First key pressed this frame with its modifiers, Escape cancels.
+pub fn captured_shortcut(ctx: &egui::Context) -> Option<Option<KeyboardShortcut>> {
+    ctx.input(|i| {
+        i.events.iter().find_map(|e| match e {
+            egui::Event::Key { key: Key::Escape, pressed: true, .. } => Some(None),
+            egui::Event::Key { key, pressed: true, modifiers, .. } => Some(Some(KeyboardShortcut::new(*modifiers, *key))),
+            _ => None,
+        })
+    })
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use crate::bill_reader::{Transaction, JOINT_TAG, PERSONAL_TAG};
+
+/// People sharing the bill. Personal transactions belong to the first person (the owner of the
+/// personal card), joint transactions are split evenly between everyone.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct SplitSettings {
+    pub people: Vec<String>,
+}
+
+impl Default for SplitSettings {
+    fn default() -> Self {
+        Self {
+            people: vec!["Me".to_string(), "Partner".to_string()],
+        }
+    }
+}
+
+impl SplitSettings {
+    /// Share of the amount owed by each person, untagged transactions are not owed by anyone.
+    /// Shares are split in cents and the leftover cents go to the first people so they always add
+    /// up to the transaction amount.
+    pub fn shares(&self, transaction: &Transaction) -> Vec<(String, f64)> {
+        let people = if transaction.tags.iter().any(|t| t == JOINT_TAG) {
+            &self.people[..]
+        } else if transaction.tags.iter().any(|t| t == PERSONAL_TAG) {
+            &self.people[..self.people.len().min(1)]
+        } else {
+            &[]
+        };
+        if people.is_empty() {
+            return Vec::new();
+        }
+
+        let cents = (transaction.amount * 100.0).round() as i64;
+        let count = people.len() as i64;
+        people.iter()
+            .enumerate()
+            .map(|(index, person)| {
+                let share = cents.div_euclid(count) + if (index as i64) < cents.rem_euclid(count) { 1 } else { 0 };
+                (person.clone(), share as f64 / 100.0)
+            })
+            .collect()
+    }
+}
+
+/// "05 JUN to 28 JUN", statements are listed in date order.
+pub fn period(transactions: &[Transaction]) -> String {
+    match (transactions.first(), transactions.last()) {
+        (Some(first), Some(last)) => format!("{} to {}", first.date, last.date),
+        _ => "No transactions".to_string(),
+    }
+}
+
+/// Money owed by one person to another to settle a statement.
+pub struct Settlement {
+    pub from: String,
+    pub to: String,
+    pub amount: f64,
+}
+
+/// Totals of a list of transactions, used by the exports.
+pub struct Summary {
+    pub count: usize,
+    pub total: f64,
+    pub per_tag: BTreeMap<String, f64>,
+    /// Totals of each card, by how its first transaction prints the card.
+    pub per_card: BTreeMap<String, f64>,
+    pub per_person: BTreeMap<String, f64>,
+}
+
+impl Summary {
+    pub fn new(transactions: &[Transaction], settings: &SplitSettings) -> Self {
+        let mut summary = Self {
+            count: transactions.len(),
+            total: 0.0,
+            per_tag: BTreeMap::new(),
+            per_card: BTreeMap::new(),
+            per_person: settings.people.iter().map(|p| (p.clone(), 0.0)).collect(),
+        };
+
+        //card keys to the card as the first transaction prints it
+        let mut cards = BTreeMap::<String, String>::new();
+        for transaction in transactions {
+            summary.total += transaction.amount;
+            for tag in &transaction.tags {
+                *summary.per_tag.entry(tag.clone()).or_default() += transaction.amount;
+            }
+            let card = cards.entry(transaction.card_key()).or_insert_with(|| transaction.card.clone());
+            *summary.per_card.entry(card.clone()).or_default() += transaction.amount;
+            for (person, share) in settings.shares(transaction) {
+                *summary.per_person.entry(person).or_default() += share;
+            }
+        }
+
+        summary
+    }
+
+    /// The first person pays the bill, so everyone else transfers their share to them.
+    pub fn settlements(&self, settings: &SplitSettings) -> Vec<Settlement> {
+        let Some(payer) = settings.people.first() else {
+            return Vec::new();
+        };
+        settings.people.iter()
+            .skip(1)
+            .filter(|p| *p != payer)
+            .filter_map(|person| {
+                let amount = self.per_person.get(person).copied().unwrap_or_default();
+                (amount.abs() >= 0.005).then(|| Settlement {
+                    from: person.clone(),
+                    to: payer.clone(),
+                    amount,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(card: &str, card_id: &str, amount: f64) -> Transaction {
+        let mut transaction = Transaction::new("05 JUN".to_string(), "GRAB".to_string(), amount, card.to_string(), vec![JOINT_TAG.to_string()]);
+        transaction.card_id = card_id.to_string();
+        transaction
+    }
+
+    #[test]
+    fn cards_are_grouped_by_id() {
+        let transactions = [
+            transaction("1234 5678 9012 5136", "1234567890125136", 10.0),
+            transaction("1234-5678-9012-5136", "1234567890125136", 5.0),
+            transaction("4321 8765 2109 0005", "", 2.0),
+            transaction("4321876521090005", "", 1.0),
+        ];
+        let summary = Summary::new(&transactions, &SplitSettings::default());
+        assert_eq!(summary.per_card.into_iter().collect::<Vec<(String, f64)>>(), vec![
+            ("1234 5678 9012 5136".to_string(), 15.0),
+            ("4321 8765 2109 0005".to_string(), 3.0),
+        ]);
+    }
+}
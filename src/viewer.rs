@@ -0,0 +1,135 @@
+use std::sync::{Arc, Mutex};
+use egui::{Color32, ColorImage, Rect, Stroke, TextureHandle, TextureOptions};
+use pdfium_render::prelude::{PdfRenderConfig, Pdfium};
+use crate::attachment::Attachment;
+use crate::bill_reader::{self, Source};
+
+//pixel width pages are rendered at, scaled down to the panel width when shown
+const RENDER_WIDTH: i32 = 1200;
+
+struct Page {
+    texture: TextureHandle,
+    width: f32,
+    height: f32,
+}
+
+/// Statement content loaded from the attachment store, filled in by the app's async loader.
+pub type Loaded = Arc<Mutex<Option<Result<Vec<u8>, String>>>>;
+
+/// Pages of one statement PDF rendered to textures, with the line of the selected transaction
+/// highlighted.
+#[derive(Default)]
+pub struct StatementViewer {
+    pdfium: Option<Pdfium>,
+    statement: Option<Attachment>,
+    loaded: Loaded,
+    pages: Vec<Page>,
+    error: Option<String>,
+    //source the view last scrolled to
+    shown: Option<Source>,
+}
+
+impl StatementViewer {
+    /// Whether `statement` still has to be loaded before it can be shown.
+    pub fn needs(&self, statement: &Attachment) -> bool {
+        self.statement.as_ref() != Some(statement)
+    }
+
+    /// Forgets the current statement and hands out the slot the content of `statement` goes in.
+    pub fn load(&mut self, statement: Attachment) -> Loaded {
+        self.statement = Some(statement);
+        self.loaded = Arc::default();
+        self.pages.clear();
+        self.error = None;
+        self.shown = None;
+        Arc::clone(&self.loaded)
+    }
+
+    fn render(&mut self, ctx: &egui::Context, data: Vec<u8>) {
+        if self.pdfium.is_none() {
+            match bill_reader::bind_pdfium() {
+                Ok(pdfium) => self.pdfium = Some(pdfium),
+                Err(e) => {
+                    self.error = Some(format!("Showing pages needs the pdfium library: {}", e));
+                    return;
+                }
+            }
+        }
+        let Some(pdfium) = &self.pdfium else {
+            return;
+        };
+        let document = match pdfium.load_pdf_from_byte_vec(data, None) {
+            Ok(document) => document,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return;
+            }
+        };
+        let id = self.statement.as_ref().map(|s| s.id.clone()).unwrap_or_default();
+        let config = PdfRenderConfig::new().set_target_width(RENDER_WIDTH);
+        for (index, page) in document.pages().iter().enumerate() {
+            match page.render_with_config(&config) {
+                Ok(bitmap) => {
+                    let size = [bitmap.width() as usize, bitmap.height() as usize];
+                    let image = ColorImage::from_rgba_unmultiplied(size, &bitmap.as_rgba_bytes());
+                    self.pages.push(Page {
+                        texture: ctx.load_texture(format!("{}-{}", id, index), image, TextureOptions::LINEAR),
+                        width: page.width().value,
+                        height: page.height().value,
+                    });
+                }
+                Err(e) => {
+                    self.error = Some(format!("Page {}: {}", index + 1, e));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Shows every page, scrolling to the source line when it changed since the last frame.
+    pub fn show(&mut self, ui: &mut egui::Ui, source: &Source) {
+        let loaded = self.loaded.lock().unwrap().take();
+        match loaded {
+            Some(Ok(data)) => self.render(ui.ctx(), data),
+            Some(Err(e)) => self.error = Some(e),
+            None => {}
+        }
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+            return;
+        }
+        if self.pages.is_empty() {
+            ui.spinner();
+            return;
+        }
+
+        let scroll = self.shown.as_ref() != Some(source);
+        self.shown = Some(source.clone());
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for (index, page) in self.pages.iter().enumerate() {
+                    let width = ui.available_width();
+                    let scale = width / page.width;
+                    let response = ui.image((page.texture.id(), egui::vec2(width, page.height * scale)));
+                    if index != source.page {
+                        continue;
+                    }
+                    //PDF points run up from the bottom of the page, screen points down from the top
+                    let target = match source.bounds {
+                        Some([left, bottom, right, top]) => Rect::from_min_max(
+                            response.rect.min + egui::vec2(left * scale, (page.height - top) * scale),
+                            response.rect.min + egui::vec2(right * scale, (page.height - bottom) * scale),
+                        ).expand(2.0),
+                        None => response.rect,
+                    };
+                    if source.bounds.is_some() {
+                        ui.painter().rect(target, 2.0, Color32::from_rgba_unmultiplied(255, 200, 0, 60), Stroke::new(2.0, Color32::from_rgb(230, 140, 0)));
+                    }
+                    if scroll {
+                        ui.scroll_to_rect(target, Some(egui::Align::Center));
+                    }
+                }
+            });
+    }
+}
@@ -0,0 +1,36 @@
+//! Golden statements: the text in `tests/fixtures/<name>.txt` parses to the statement in
+//! `tests/fixtures/<name>.json`, no pdfium needed.
+
+use credit_card_billsplit::bill_reader::{PlainTextExtractor, ReaderOptions, StatementParser, StatementProfile};
+
+fn fixture(name: &str) -> String {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+fn assert_parses_to_fixture(name: &str, options: ReaderOptions) {
+    let pages = PlainTextExtractor::pages(&fixture(&format!("{}.txt", name)));
+    let statement = serde_json::to_value(StatementParser::new(options).parse(&pages)).unwrap();
+    let expected = serde_json::from_str::<serde_json::Value>(&fixture(&format!("{}.json", name))).unwrap();
+    assert_eq!(statement, expected, "{}.txt parsed to\n{}", name, serde_json::to_string_pretty(&statement).unwrap());
+}
+
+/// The card number carries over the page break, lines of a card section which are not
+/// transactions end up in `unmatched_lines`.
+#[test]
+fn citibank() {
+    assert_parses_to_fixture("citibank", ReaderOptions::default());
+}
+
+#[test]
+fn no_profile_matches() {
+    let options = ReaderOptions {
+        profiles: vec![StatementProfile {
+            name: "Other Bank".to_string(),
+            fingerprints: vec!["Other Bank".to_string()],
+            ..StatementProfile::citibank()
+        }],
+        ..Default::default()
+    };
+    assert_parses_to_fixture("example_bank", options);
+}